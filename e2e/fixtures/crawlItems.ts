@@ -1,3 +1,5 @@
+import type { Page } from '@playwright/test';
+
 export interface CrawlItem {
   id: string;
   source: string;
@@ -5,7 +7,7 @@ export interface CrawlItem {
   title: string;
   url: string;
   thumbnail_url: string | null;
-  thumbnail_data: string | null;
+  thumbnail_hash: string | null;
   description: string | null;
   fetched_at: string;
   is_seen: boolean;
@@ -13,7 +15,9 @@ export interface CrawlItem {
   session_date: string;
 }
 
-const TINY_PNG_BASE64 = 'data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAgAAAAICAYAAADED76LAAAAFklEQVQoU2NkYGD4z0AEYBw1YNQAJgBNSQEJWDQmhwAAAABJRU5ErkJggg==';
+const TINY_PNG_BASE64 = 'iVBORw0KGgoAAAANSUhEUgAAAAgAAAAICAYAAADED76LAAAAFklEQVQoU2NkYGD4z0AEYBw1YNQAJgBNSQEJWDQmhwAAAABJRU5ErkJggg==';
+/** SHA-256 of the PNG above, the key it has in the image store. */
+export const TINY_PNG_HASH = 'e1986d26aa8fbcad74555537f1c60044d82949c28fb492ccbbce44fd8af0393b';
 
 const today = new Date().toISOString().split('T')[0];
 const now = new Date().toISOString();
@@ -25,8 +29,8 @@ export const TEST_ITEMS: CrawlItem[] = [
     category: 'meme',
     title: 'Test Meme: When You Finally Fix That Bug',
     url: 'https://example.com/meme/001',
-    thumbnail_url: null,
-    thumbnail_data: TINY_PNG_BASE64,
+    thumbnail_url: 'https://i.redd.it/test-meme-001.png',
+    thumbnail_hash: TINY_PNG_HASH,
     description: 'Classic programming meme',
     fetched_at: now,
     is_seen: false,
@@ -40,7 +44,7 @@ export const TEST_ITEMS: CrawlItem[] = [
     title: 'Test Joke',
     url: 'https://example.com/joke/001',
     thumbnail_url: null,
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'Why do programmers prefer dark mode? Because light attracts bugs!',
     fetched_at: now,
     is_seen: false,
@@ -53,8 +57,8 @@ export const TEST_ITEMS: CrawlItem[] = [
     category: 'news',
     title: 'Test News: Major Tech Breakthrough Announced',
     url: 'https://example.com/news/001',
-    thumbnail_url: null,
-    thumbnail_data: TINY_PNG_BASE64,
+    thumbnail_url: 'https://example.com/news/001/lead.png',
+    thumbnail_hash: TINY_PNG_HASH,
     description: 'Scientists discover new algorithm that makes tests run faster. Revolutionary breakthrough in software testing methodology.',
     fetched_at: now,
     is_seen: false,
@@ -67,8 +71,8 @@ export const TEST_ITEMS: CrawlItem[] = [
     category: 'video',
     title: 'Test Video: How to Write Better Tests',
     url: 'https://example.com/video/001',
-    thumbnail_url: null,
-    thumbnail_data: TINY_PNG_BASE64,
+    thumbnail_url: 'https://example.com/video/001/poster.png',
+    thumbnail_hash: TINY_PNG_HASH,
     description: 'Educational video about test-driven development',
     fetched_at: now,
     is_seen: false,
@@ -82,7 +86,7 @@ export const TEST_ITEMS: CrawlItem[] = [
     title: 'Test Celebrity Spotted Using New Framework',
     url: 'https://example.com/gossip/001',
     thumbnail_url: null,
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'Exclusive: Famous developer seen contributing to open source',
     fetched_at: now,
     is_seen: false,
@@ -99,7 +103,7 @@ export const TEST_ITEMS_WITH_URLS: CrawlItem[] = [
     title: 'Test Meme: Valid Placeholder URL Thumbnail',
     url: 'https://example.com/meme/url-001',
     thumbnail_url: 'https://via.placeholder.com/150',
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'Meme with valid HTTPS thumbnail URL',
     fetched_at: now,
     is_seen: false,
@@ -113,7 +117,7 @@ export const TEST_ITEMS_WITH_URLS: CrawlItem[] = [
     title: 'Test News: Protocol-Relative URL Thumbnail',
     url: 'https://example.com/news/protocol-001',
     thumbnail_url: '//via.placeholder.com/150',
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'News with protocol-relative URL (//)',
     fetched_at: now,
     is_seen: false,
@@ -127,7 +131,7 @@ export const TEST_ITEMS_WITH_URLS: CrawlItem[] = [
     title: 'Test Video: Failing Thumbnail URL (404)',
     url: 'https://example.com/video/404-001',
     thumbnail_url: 'https://example.com/nonexistent.jpg',
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'Video with invalid thumbnail that will 404',
     fetched_at: now,
     is_seen: false,
@@ -141,7 +145,7 @@ export const TEST_ITEMS_WITH_URLS: CrawlItem[] = [
     title: 'Test Gossip: No Thumbnail Available',
     url: 'https://example.com/gossip/no-thumb-001',
     thumbnail_url: null,
-    thumbnail_data: null,
+    thumbnail_hash: null,
     description: 'Gossip item with both thumbnail fields null',
     fetched_at: now,
     is_seen: false,
@@ -169,3 +173,25 @@ export function getTestStoreStateWithUrls() {
     activeCategory: 'all' as const,
   };
 }
+
+/**
+ * Serves the image store the way the `cazz` scheme does, holding only the
+ * tiny PNG. Playwright can't route custom schemes, so the page is made to
+ * look like Windows, where `thumbnailSrc` uses `http://cazz.localhost`.
+ */
+export async function serveImageStore(page: Page) {
+  await page.context().addInitScript(() => {
+    const userAgent = `${navigator.userAgent} (Windows NT 10.0)`;
+    Object.defineProperty(navigator, 'userAgent', { get: () => userAgent });
+  });
+  await page.route('http://cazz.localhost/image/**', (route) => {
+    const hash = new URL(route.request().url()).pathname.replace('/image/', '');
+    if (hash === TINY_PNG_HASH) {
+      return route.fulfill({
+        contentType: 'image/png',
+        body: Buffer.from(TINY_PNG_BASE64, 'base64'),
+      });
+    }
+    return route.fulfill({ status: 404, contentType: 'text/plain', body: 'image not stored' });
+  });
+}
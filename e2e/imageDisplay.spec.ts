@@ -1,8 +1,9 @@
 import { test, expect } from '@playwright/test';
-import { getTestStoreState } from './fixtures/crawlItems';
+import { TINY_PNG_HASH, getTestStoreState, serveImageStore } from './fixtures/crawlItems';
 
 test.describe('Image Display Verification', () => {
   test.beforeEach(async ({ page }) => {
+    await serveImageStore(page);
     await page.context().addInitScript(() => {
       localStorage.setItem("cazzmachine_first_run", "false");
     });
@@ -34,4 +35,12 @@ test.describe('Image Display Verification', () => {
     const previewImg = preview.locator('img').first();
     await expect(previewImg).toBeVisible();
   });
+
+  test('should load stored thumbnails from the image store by hash', async ({ page }) => {
+    const item = page.locator('.flex.gap-4.p-3').filter({ hasText: 'When You Finally Fix That Bug' }).first();
+    const thumbnailImg = item.locator('img.w-full.h-full');
+
+    await expect(thumbnailImg).toHaveAttribute('src', new RegExp(`^http://cazz\\.localhost/image/${TINY_PNG_HASH}\\?src=`));
+    await expect.poll(() => thumbnailImg.evaluate((img: HTMLImageElement) => img.naturalWidth)).toBe(8);
+  });
 });
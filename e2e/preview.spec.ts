@@ -1,8 +1,9 @@
 import { test, expect } from '@playwright/test';
-import { getTestStoreState } from './fixtures/crawlItems';
+import { getTestStoreState, serveImageStore } from './fixtures/crawlItems';
 
 test.describe('Hover Preview Functionality', () => {
   test.beforeEach(async ({ page }) => {
+    await serveImageStore(page);
     await page.context().addInitScript(() => {
       localStorage.setItem("cazzmachine_first_run", "false");
     });
@@ -1,8 +1,9 @@
 import { test, expect } from '@playwright/test';
-import { getTestStoreStateWithUrls } from './fixtures/crawlItems';
+import { getTestStoreStateWithUrls, serveImageStore } from './fixtures/crawlItems';
 
 test.describe('Thumbnail Edge Cases', () => {
   test.beforeEach(async ({ page }) => {
+    await serveImageStore(page);
     await page.context().addInitScript(() => {
       localStorage.setItem("cazzmachine_first_run", "false");
     });
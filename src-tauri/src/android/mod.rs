@@ -1,5 +1,3 @@
-pub mod background_service;
-
 use std::sync::Arc;
 use tauri::State;
 use crate::db::Database;
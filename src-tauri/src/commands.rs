@@ -1,16 +1,21 @@
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::Arc;
 use tauri::State;
-use base64::{engine::general_purpose::STANDARD, Engine};
 
 use crate::db::models::{
-    ClearDiagnosticsResult, ConsumeResult, CrawlItem, DayStats, DaySummary, DiagnosticLog,
-    DiagnosticSummary, ProviderStatus,
+    ClearDiagnosticsResult, ConsumeResult, CrawlItem, DailyStats, DayStats, DaySummary,
+    DiagnosticLog, DiagnosticSummary, FilterRule, ItemDuplicate, LibraryFilters, LibraryPage,
+    Paging, ProviderStatus, SearchFilters, SearchResults, Source, StatsPeriod, StatsSummary,
 };
 use crate::db::Database;
+use crate::export::bundle::{self, BundleSummary, ImportReport};
+use crate::export::{self, ExportFormat, ExportReport};
+use crate::images;
+use crate::net::HttpService;
+use crate::settings::Settings;
 use crate::summary;
 use crate::crawler;
-use crate::crawler::provider::ContentProvider;
+use crate::crawler::registry::ProviderRegistry;
 
 pub static THROTTLE_LEVEL: AtomicU8 = AtomicU8::new(5);
 pub static THREAD_COUNT: AtomicU8 = AtomicU8::new(1);
@@ -22,8 +27,13 @@ pub fn get_throttle_level() -> u8 {
 
 #[tauri::command]
 pub async fn set_throttle_level(db: State<'_, Arc<Database>>, level: u8) -> Result<(), String> {
-    let level = level.clamp(1, 9);
-    THROTTLE_LEVEL.store(level, Ordering::Relaxed);
+    let settings = Settings {
+        throttle_level: level,
+        ..Settings::current()
+    }
+    .persist(&db)
+    .map_err(|e| e.to_string())?;
+    let level = settings.throttle_level;
     db.log_diagnostic_event("setting_change", "info", &format!("Throttle level set to {}", level), None, None)
         .map_err(|e| e.to_string())
 }
@@ -35,12 +45,30 @@ pub fn get_consumption_threads() -> u8 {
 
 #[tauri::command]
 pub async fn set_consumption_threads(db: State<'_, Arc<Database>>, count: u8) -> Result<(), String> {
-    let count = count.clamp(1, 8);
-    THREAD_COUNT.store(count, Ordering::Relaxed);
+    let settings = Settings {
+        consumption_threads: count,
+        ..Settings::current()
+    }
+    .persist(&db)
+    .map_err(|e| e.to_string())?;
+    let count = settings.consumption_threads;
     db.log_diagnostic_event("setting_change", "info", &format!("Thread count set to {}", count), None, None)
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    Settings::current()
+}
+
+#[tauri::command]
+pub async fn update_settings(db: State<'_, Arc<Database>>, settings: Settings) -> Result<Settings, String> {
+    let settings = settings.persist(&db).map_err(|e| e.to_string())?;
+    db.log_diagnostic_event("setting_change", "info", &format!("Settings updated: {:?}", settings), None, None)
+        .map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
 #[tauri::command]
 pub async fn get_today_items(db: State<'_, Arc<Database>>) -> Result<Vec<CrawlItem>, String> {
     db.get_items_for_today().map_err(|e| e.to_string())
@@ -55,11 +83,38 @@ pub async fn get_items_by_category(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn search_items(
+    db: State<'_, Arc<Database>>,
+    query: String,
+    filters: Option<SearchFilters>,
+    paging: Option<Paging>,
+) -> Result<SearchResults, String> {
+    db.search_items(&query, &filters.unwrap_or_default(), &paging.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_today_stats(db: State<'_, Arc<Database>>) -> Result<DayStats, String> {
     db.get_today_stats().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_stats_summary(
+    db: State<'_, Arc<Database>>,
+    period: StatsPeriod,
+) -> Result<StatsSummary, String> {
+    db.get_stats_summary(period).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_stats_series(
+    db: State<'_, Arc<Database>>,
+    period: StatsPeriod,
+) -> Result<Vec<DailyStats>, String> {
+    db.get_stats_series(period).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_daily_summary(db: State<'_, Arc<Database>>) -> Result<DaySummary, String> {
     summary::generate_daily_summary(&db)
@@ -70,6 +125,119 @@ pub async fn toggle_save_item(db: State<'_, Arc<Database>>, item_id: String) ->
     db.toggle_item_saved(&item_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_saved_items(
+    db: State<'_, Arc<Database>>,
+    filters: Option<LibraryFilters>,
+    paging: Option<Paging>,
+) -> Result<LibraryPage, String> {
+    db.get_saved_items(&filters.unwrap_or_default(), &paging.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_saved_item_note(
+    db: State<'_, Arc<Database>>,
+    item_id: String,
+    note: Option<String>,
+) -> Result<(), String> {
+    match db.set_library_note(&item_id, note.as_deref()) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Item {} is not saved", item_id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn set_saved_item_tags(
+    db: State<'_, Arc<Database>>,
+    item_id: String,
+    tags: Vec<String>,
+) -> Result<Vec<String>, String> {
+    db.set_library_tags(&item_id, &tags)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Item {} is not saved", item_id))
+}
+
+/// Copies of an item seen from other sources, which were not stored.
+#[tauri::command]
+pub async fn get_item_duplicates(
+    db: State<'_, Arc<Database>>,
+    item_id: String,
+) -> Result<Vec<ItemDuplicate>, String> {
+    db.get_item_duplicates(&item_id).map_err(|e| e.to_string())
+}
+
+/// Dates are optional inclusive YYYY-MM-DD bounds.
+#[tauri::command]
+pub async fn export_data(
+    db: State<'_, Arc<Database>>,
+    format: ExportFormat,
+    path: String,
+    from_date: Option<String>,
+    to_date: Option<String>,
+) -> Result<ExportReport, String> {
+    let report = export::export(
+        &db,
+        format,
+        std::path::Path::new(&path),
+        from_date.as_deref(),
+        to_date.as_deref(),
+    )?;
+    let _ = db.log_diagnostic_event(
+        "export",
+        "info",
+        &format!(
+            "Exported {} saved items, {} history items and {} days to {}",
+            report.saved_items,
+            report.history_items,
+            report.days,
+            report.files.join(", ")
+        ),
+        None,
+        None,
+    );
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn export_bundle(db: State<'_, Arc<Database>>, path: String) -> Result<BundleSummary, String> {
+    let summary = bundle::write(&db, std::path::Path::new(&path))?;
+    let _ = db.log_diagnostic_event(
+        "export",
+        "info",
+        &format!("Wrote backup bundle {} ({} saved items)", summary.path, summary.saved_items),
+        None,
+        None,
+    );
+    Ok(summary)
+}
+
+/// With `dry_run`, reports what the import would change without writing.
+#[tauri::command]
+pub async fn import_bundle(
+    db: State<'_, Arc<Database>>,
+    path: String,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    let bundle = bundle::read(std::path::Path::new(&path))?;
+    let report = bundle::import(&db, &bundle, dry_run)?;
+    if !dry_run {
+        Settings::load(&db).map_err(|e| e.to_string())?.apply();
+        let _ = db.log_diagnostic_event(
+            "import",
+            "info",
+            &format!(
+                "Imported bundle {}: {} saved items, {} sources, {} filters, {} days added",
+                path, report.saved.added, report.sources.added, report.filters.added, report.daily_stats.added
+            ),
+            None,
+            None,
+        );
+    }
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn mark_item_seen(db: State<'_, Arc<Database>>, item_id: String) -> Result<(), String> {
     db.mark_item_seen(&item_id).map_err(|e| e.to_string())
@@ -149,75 +317,8 @@ pub async fn clear_diagnostics(
 }
 
 #[tauri::command]
-pub async fn trigger_crawl(db: State<'_, Arc<Database>>) -> Result<u32, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| e.to_string())?;
-    
-    let providers: Vec<Box<dyn ContentProvider>> = vec![
-        Box::new(crawler::reddit::RedditProvider::memes()),
-        Box::new(crawler::reddit::RedditProvider::dad_jokes()),
-        Box::new(crawler::reddit::RedditProvider::celebrity_gossip()),
-        Box::new(crawler::dadjoke::DadJokeProvider),
-        Box::new(crawler::meme::RedditMemeProvider),
-        Box::new(crawler::video::RedditVideoProvider),
-        Box::new(crawler::gossip::GossipProvider),
-        Box::new(crawler::news::GoogleNewsRssProvider),
-        Box::new(crawler::jokeapi::JokeApiProvider),
-        Box::new(crawler::uselessfacts::UselessFactsProvider),
-        Box::new(crawler::chucknorris::ChuckNorrisProvider),
-        Box::new(crawler::hackernews::HackerNewsProvider),
-        Box::new(crawler::bbcnews::BbcNewsProvider),
-    ];
-    
-    let count = crawler::providers_per_cycle();
-    let provider_idx = rand::random::<usize>() % providers.len();
-    let mut items_added = 0u32;
-    
-    for i in 0..count {
-        let idx = (provider_idx + i) % providers.len();
-        let provider = &providers[idx];
-        let provider_name = provider.name();
-
-        let items = provider.fetch(&client).await;
-        let item_count = items.len();
-
-        for item in items {
-            let crawl_item = item.into_crawl_item();
-            match db.insert_item(&crawl_item) {
-                Ok(true) => items_added += 1,
-                Ok(false) => {}
-                Err(e) => {
-                    let _ = db.log_diagnostic_event(
-                        "insert_error",
-                        "warn",
-                        &format!("Failed to insert item: {}", e),
-                        None,
-                        None
-                    );
-                }
-            }
-        }
-
-        if item_count > 0 {
-            let _ = db.log_diagnostic_event(
-                "crawl_success",
-                "info",
-                &format!("{}: fetched {} items", provider_name, item_count),
-                None,
-                None
-            );
-        } else {
-            let _ = db.log_diagnostic_event(
-                "crawl_error",
-                "warn",
-                &format!("{}: no items fetched", provider_name),
-                None,
-                None
-            );
-        }
-    }
+pub async fn trigger_crawl(db: State<'_, Arc<Database>>, http: State<'_, Arc<HttpService>>) -> Result<u32, String> {
+    let items_added = crawler::run_cycle(&db, &http).await;
 
     let _ = db.log_diagnostic_event(
         "crawl_complete",
@@ -229,6 +330,93 @@ pub async fn trigger_crawl(db: State<'_, Arc<Database>>) -> Result<u32, String>
     Ok(items_added)
 }
 
+#[tauri::command]
+pub async fn list_sources(db: State<'_, Arc<Database>>) -> Result<Vec<Source>, String> {
+    db.list_sources().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_source(
+    db: State<'_, Arc<Database>>,
+    kind: String,
+    target: String,
+    category: String,
+) -> Result<Source, String> {
+    let target = crawler::sources::normalize(&kind, &target, &category)?;
+    if ProviderRegistry::load(&db).get(&target).is_some() {
+        return Err(format!("'{}' is already a source", target));
+    }
+
+    let source = db.add_source(&kind, &target, &category).map_err(|e| e.to_string())?;
+    db.log_diagnostic_event("source_change", "info", &format!("Source added: {} {} ({})", kind, target, category), None, None)
+        .map_err(|e| e.to_string())?;
+    Ok(source)
+}
+
+#[tauri::command]
+pub async fn remove_source(db: State<'_, Arc<Database>>, source_id: String) -> Result<(), String> {
+    if !db.remove_source(&source_id).map_err(|e| e.to_string())? {
+        return Err(format!("No source with id {}", source_id));
+    }
+    db.log_diagnostic_event("source_change", "info", &format!("Source removed: {}", source_id), None, None)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_source_enabled(
+    db: State<'_, Arc<Database>>,
+    source_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    if !db.set_source_enabled(&source_id, enabled).map_err(|e| e.to_string())? {
+        return Err(format!("No source with id {}", source_id));
+    }
+    let action = if enabled { "enabled" } else { "disabled" };
+    db.log_diagnostic_event("source_change", "info", &format!("Source {}: {}", action, source_id), None, None)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_filter_rules(db: State<'_, Arc<Database>>) -> Result<Vec<FilterRule>, String> {
+    db.list_filter_rules().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_filter_rule(
+    db: State<'_, Arc<Database>>,
+    kind: String,
+    pattern: String,
+) -> Result<FilterRule, String> {
+    let pattern = crawler::filters::normalize(&kind, &pattern)?;
+    let rule = db.add_filter_rule(&kind, &pattern).map_err(|e| e.to_string())?;
+    db.log_diagnostic_event("filter_change", "info", &format!("Filter added: {} \"{}\"", kind, pattern), None, None)
+        .map_err(|e| e.to_string())?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn remove_filter_rule(db: State<'_, Arc<Database>>, rule_id: String) -> Result<(), String> {
+    if !db.remove_filter_rule(&rule_id).map_err(|e| e.to_string())? {
+        return Err(format!("No filter with id {}", rule_id));
+    }
+    db.log_diagnostic_event("filter_change", "info", &format!("Filter removed: {}", rule_id), None, None)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_filter_rule_enabled(
+    db: State<'_, Arc<Database>>,
+    rule_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    if !db.set_filter_rule_enabled(&rule_id, enabled).map_err(|e| e.to_string())? {
+        return Err(format!("No filter with id {}", rule_id));
+    }
+    let action = if enabled { "enabled" } else { "disabled" };
+    db.log_diagnostic_event("filter_change", "info", &format!("Filter {}: {}", action, rule_id), None, None)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn log_diagnostic(
     db: State<'_, Arc<Database>>,
@@ -242,15 +430,9 @@ pub async fn log_diagnostic(
 }
 
 #[tauri::command]
-pub async fn fetch_image(url: String) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    let response = client
+pub async fn fetch_image(http: State<'_, Arc<HttpService>>, url: String) -> Result<String, String> {
+    let response = http
         .get(&url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
         .header("Referer", "https://www.reddit.com/")
         .send()
         .await
@@ -259,22 +441,19 @@ pub async fn fetch_image(url: String) -> Result<String, String> {
     if !response.status().is_success() {
         return Err(format!("Image fetch failed with status: {}", response.status()));
     }
+    if response.content_length().is_some_and(|len| len > images::MAX_DOWNLOAD_BYTES as u64) {
+        return Err("Image is too large".to_string());
+    }
 
     let bytes = response
         .bytes()
         .await
         .map_err(|e| format!("Failed to read image bytes: {}", e))?;
 
-    let mime = if url.contains(".png") {
-        "image/png"
-    } else if url.contains(".gif") {
-        "image/gif"
-    } else if url.contains(".webp") {
-        "image/webp"
-    } else {
-        "image/jpeg"
-    };
-
-    let base64 = STANDARD.encode(&bytes);
-    Ok(format!("data:{};base64,{}", mime, base64))
+    let max_px = images::thumbnail_max_px();
+    let image = tokio::task::spawn_blocking(move || images::thumbnail(&bytes, max_px))
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Not a supported image, or too large".to_string())?;
+    Ok(image.to_data_url())
 }
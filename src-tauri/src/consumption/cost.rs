@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Bounds for any single item's cost, in minutes.
+const MIN_COST: f64 = 0.05;
+const MAX_COST: f64 = 240.0;
+
+/// How many minutes a human would spend on an item.
+///
+/// Resolution order, first match wins:
+/// 1. a per-source override (keyed by the item's `source`, e.g. `r/memes`)
+/// 2. the video's duration, for videos whose duration is known
+/// 3. word count / `reading_words_per_minute`, for text categories when a
+///    reading speed is set
+/// 4. the per-category cost
+/// 5. `default_minutes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CostModel {
+    pub categories: BTreeMap<String, f64>,
+    pub sources: BTreeMap<String, f64>,
+    pub default_minutes: f64,
+    /// `None` keeps text items at their category cost.
+    pub reading_words_per_minute: Option<f64>,
+    pub text_categories: Vec<String>,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        let categories = [("meme", 0.5), ("joke", 0.3), ("news", 2.0), ("video", 3.0), ("gossip", 1.5)]
+            .into_iter()
+            .map(|(category, minutes)| (category.to_string(), minutes))
+            .collect();
+        Self {
+            categories,
+            sources: BTreeMap::new(),
+            default_minutes: 1.0,
+            reading_words_per_minute: None,
+            text_categories: vec!["joke".into(), "news".into(), "gossip".into()],
+        }
+    }
+}
+
+static CURRENT: OnceLock<RwLock<CostModel>> = OnceLock::new();
+
+fn current_lock() -> &'static RwLock<CostModel> {
+    CURRENT.get_or_init(|| RwLock::new(CostModel::default()))
+}
+
+impl CostModel {
+    /// The model currently in effect, mirrored from settings.
+    pub fn current() -> Self {
+        current_lock().read().unwrap().clone()
+    }
+
+    pub fn apply(&self) {
+        *current_lock().write().unwrap() = self.clone();
+    }
+
+    /// Keeps every cost within sane bounds and normalises keys.
+    pub fn clamped(self) -> Self {
+        let bound = |minutes: f64| {
+            if minutes.is_finite() {
+                minutes.clamp(MIN_COST, MAX_COST)
+            } else {
+                1.0
+            }
+        };
+        let clean = |map: BTreeMap<String, f64>, lowercase: bool| {
+            map.into_iter()
+                .map(|(key, minutes)| {
+                    let key = key.trim().to_string();
+                    (if lowercase { key.to_lowercase() } else { key }, bound(minutes))
+                })
+                .filter(|(key, _)| !key.is_empty())
+                .collect()
+        };
+        Self {
+            categories: clean(self.categories, true),
+            sources: clean(self.sources, false),
+            default_minutes: bound(self.default_minutes),
+            reading_words_per_minute: self
+                .reading_words_per_minute
+                .filter(|wpm| wpm.is_finite())
+                .map(|wpm| wpm.clamp(50.0, 1000.0)),
+            text_categories: self
+                .text_categories
+                .into_iter()
+                .map(|c| c.trim().to_lowercase())
+                .filter(|c| !c.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn category_cost(&self, category: &str) -> f64 {
+        self.categories.get(category).copied().unwrap_or(self.default_minutes)
+    }
+
+    /// Cost of the cheapest category, i.e. the smallest useful budget.
+    pub fn cheapest(&self) -> f64 {
+        self.categories
+            .values()
+            .copied()
+            .fold(self.default_minutes, f64::min)
+    }
+
+    /// Cost of one item; `text` is its title and description, `duration_secs`
+    /// its playing time when known.
+    pub fn cost(&self, category: &str, source: &str, text: &str, duration_secs: Option<f64>) -> f64 {
+        if let Some(&minutes) = self.sources.get(source) {
+            return minutes;
+        }
+        if category == "video" {
+            if let Some(secs) = duration_secs.filter(|s| s.is_finite() && *s > 0.0) {
+                return (secs / 60.0).clamp(MIN_COST, MAX_COST);
+            }
+        }
+        if let Some(wpm) = self.reading_words_per_minute {
+            let words = text.split_whitespace().count();
+            if words > 0 && self.text_categories.iter().any(|c| c == category) {
+                return (words as f64 / wpm).clamp(MIN_COST, MAX_COST);
+            }
+        }
+        self.category_cost(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_model_matches_category_table() {
+        let model = CostModel::default();
+        assert_eq!(model.cost("meme", "r/memes", "a meme", None), 0.5);
+        assert_eq!(model.cost("news", "BBC News", "long text ".repeat(500).as_str(), None), 2.0);
+        assert_eq!(model.cost("podcast", "x", "", None), 1.0);
+        assert_eq!(model.cheapest(), 0.3);
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let mut model = CostModel {
+            reading_words_per_minute: Some(200.0),
+            ..CostModel::default()
+        };
+        model.sources.insert("r/longreads".into(), 10.0);
+
+        let article = "word ".repeat(600);
+        assert_eq!(model.cost("news", "r/longreads", &article, None), 10.0);
+        assert_eq!(model.cost("news", "BBC News", &article, None), 3.0);
+        assert_eq!(model.cost("news", "BBC News", "", None), 2.0, "no text falls back to the category");
+        assert_eq!(model.cost("meme", "r/memes", &article, None), 0.5, "memes are not text items");
+        assert_eq!(model.cost("video", "YouTube", "", Some(90.0)), 1.5);
+        assert_eq!(model.cost("video", "YouTube", "", None), 3.0);
+    }
+
+    #[test]
+    fn test_clamped_bounds_costs() {
+        let mut model = CostModel {
+            default_minutes: -1.0,
+            reading_words_per_minute: Some(5.0),
+            ..CostModel::default()
+        };
+        model.categories.insert(" Podcast ".into(), 10_000.0);
+        model.sources.insert("  ".into(), 1.0);
+        let model = model.clamped();
+        assert_eq!(model.categories["podcast"], MAX_COST);
+        assert_eq!(model.default_minutes, MIN_COST);
+        assert_eq!(model.reading_words_per_minute, Some(50.0));
+        assert!(model.sources.is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+pub mod cost;
+
+pub use cost::CostModel;
+
+/// A not-yet-consumed item, as seen by a strategy.
+#[derive(Debug, Clone)]
+pub struct PendingItem {
+    pub id: String,
+    pub category: String,
+    pub source: String,
+    pub fetched_at: String,
+    /// How much the user engages with items like this one; higher is better.
+    /// Currently the number of saved items from the same source.
+    pub engagement: f64,
+    /// Minutes, from the `CostModel` in effect.
+    pub cost: f64,
+}
+
+/// Decides which pending items a consume call takes within its budget.
+///
+/// Most strategies only choose an order; the default `select` then walks it
+/// greedily, taking every item that still fits in the remaining budget.
+pub trait ConsumptionStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Indices into `pending`, most wanted first. `pending` is oldest first.
+    fn order(&self, pending: &[PendingItem]) -> Vec<usize>;
+
+    /// Indices of the items to consume, in consumption order.
+    fn select(&self, pending: &[PendingItem], budget_minutes: f64) -> Vec<usize> {
+        let mut remaining = budget_minutes;
+        let mut selected = Vec::new();
+        for index in self.order(pending) {
+            let cost = pending[index].cost;
+            if remaining >= cost {
+                remaining -= cost;
+                selected.push(index);
+            }
+        }
+        selected
+    }
+}
+
+/// Oldest first.
+pub struct FifoStrategy;
+
+impl ConsumptionStrategy for FifoStrategy {
+    fn name(&self) -> &'static str {
+        "fifo"
+    }
+
+    fn order(&self, pending: &[PendingItem]) -> Vec<usize> {
+        (0..pending.len()).collect()
+    }
+}
+
+/// One item per category in turn, oldest first within each category, so a
+/// burst from one provider doesn't crowd out the rest.
+pub struct RoundRobinStrategy;
+
+impl ConsumptionStrategy for RoundRobinStrategy {
+    fn name(&self) -> &'static str {
+        "round_robin"
+    }
+
+    fn order(&self, pending: &[PendingItem]) -> Vec<usize> {
+        let mut queues: BTreeMap<&str, VecDeque<usize>> = BTreeMap::new();
+        for (index, item) in pending.iter().enumerate() {
+            queues.entry(item.category.as_str()).or_default().push_back(index);
+        }
+
+        let mut order = Vec::with_capacity(pending.len());
+        while order.len() < pending.len() {
+            for queue in queues.values_mut() {
+                if let Some(index) = queue.pop_front() {
+                    order.push(index);
+                }
+            }
+        }
+        order
+    }
+}
+
+/// Highest engagement first; ties keep the oldest first.
+pub struct EngagementFirstStrategy;
+
+impl ConsumptionStrategy for EngagementFirstStrategy {
+    fn name(&self) -> &'static str {
+        "engagement_first"
+    }
+
+    fn order(&self, pending: &[PendingItem]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..pending.len()).collect();
+        // Stable sort, so equal engagement stays in FIFO order
+        order.sort_by(|&a, &b| pending[b].engagement.total_cmp(&pending[a].engagement));
+        order
+    }
+}
+
+/// Uniformly shuffled. A seed makes the order reproducible.
+pub struct RandomStrategy {
+    pub seed: Option<u64>,
+}
+
+impl ConsumptionStrategy for RandomStrategy {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn order(&self, pending: &[PendingItem]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..pending.len()).collect();
+        match self.seed {
+            Some(seed) => order.shuffle(&mut StdRng::seed_from_u64(seed)),
+            None => order.shuffle(&mut rand::thread_rng()),
+        }
+        order
+    }
+}
+
+/// The user-selectable strategies, as stored in settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    #[default]
+    Fifo,
+    RoundRobin,
+    EngagementFirst,
+    Random,
+}
+
+/// Index into `StrategyKind::ALL` of the strategy in effect.
+static CURRENT_STRATEGY: AtomicU8 = AtomicU8::new(0);
+
+impl StrategyKind {
+    pub const ALL: [StrategyKind; 4] = [
+        StrategyKind::Fifo,
+        StrategyKind::RoundRobin,
+        StrategyKind::EngagementFirst,
+        StrategyKind::Random,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StrategyKind::Fifo => "fifo",
+            StrategyKind::RoundRobin => "round_robin",
+            StrategyKind::EngagementFirst => "engagement_first",
+            StrategyKind::Random => "random",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    pub fn strategy(self) -> Box<dyn ConsumptionStrategy> {
+        match self {
+            StrategyKind::Fifo => Box::new(FifoStrategy),
+            StrategyKind::RoundRobin => Box::new(RoundRobinStrategy),
+            StrategyKind::EngagementFirst => Box::new(EngagementFirstStrategy),
+            StrategyKind::Random => Box::new(RandomStrategy { seed: None }),
+        }
+    }
+
+    /// The strategy currently in effect, mirrored from settings.
+    pub fn current() -> Self {
+        let index = CURRENT_STRATEGY.load(Ordering::Relaxed) as usize;
+        Self::ALL.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn apply(self) {
+        let index = Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0);
+        CURRENT_STRATEGY.store(index as u8, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending(items: &[(&str, f64)]) -> Vec<PendingItem> {
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, (category, engagement))| PendingItem {
+                id: format!("item-{}", i),
+                category: category.to_string(),
+                source: "test".to_string(),
+                fetched_at: format!("2025-01-01 10:00:{:02}", i),
+                engagement: *engagement,
+                cost: CostModel::default().category_cost(category),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fifo_fills_budget_greedily() {
+        // 0.5 + 3.0 would overflow 3.0, so the video is skipped for the joke
+        let items = pending(&[("meme", 0.0), ("video", 0.0), ("joke", 0.0), ("news", 0.0)]);
+        assert_eq!(FifoStrategy.select(&items, 3.0), vec![0, 2, 3]);
+        assert!(FifoStrategy.select(&items, 0.2).is_empty());
+    }
+
+    #[test]
+    fn test_round_robin_interleaves_categories() {
+        let items = pending(&[("meme", 0.0), ("meme", 0.0), ("meme", 0.0), ("joke", 0.0), ("joke", 0.0)]);
+        assert_eq!(RoundRobinStrategy.order(&items), vec![3, 0, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_engagement_first_keeps_fifo_for_ties() {
+        let items = pending(&[("meme", 1.0), ("meme", 5.0), ("meme", 1.0), ("meme", 2.0)]);
+        assert_eq!(EngagementFirstStrategy.order(&items), vec![1, 3, 0, 2]);
+        assert_eq!(EngagementFirstStrategy.select(&items, 1.0), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_random_is_a_reproducible_permutation() {
+        let items = pending(&[("meme", 0.0); 20]);
+        let order = RandomStrategy { seed: Some(7) }.order(&items);
+        assert_eq!(order, RandomStrategy { seed: Some(7) }.order(&items));
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+        assert_ne!(order, sorted);
+    }
+
+    #[test]
+    fn test_strategy_kind_round_trips() {
+        for kind in StrategyKind::ALL {
+            assert_eq!(StrategyKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(kind.strategy().name(), kind.as_str());
+        }
+        assert_eq!(StrategyKind::parse("lifo"), None);
+    }
+}
@@ -1,30 +1,16 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
-use super::util::{fetch_json, strip_html};
-use serde::Deserialize;
+use crate::net::HttpService;
+use super::rss::RssProvider;
 
-pub struct BbcNewsProvider;
-
-#[derive(Deserialize)]
-struct Rss2Json {
-    #[allow(dead_code)]
-    status: String,
-    #[allow(dead_code)]
-    feed: Option<RssFeed>,
-    items: Option<Vec<RssItem>>,
-}
+const FEED_URL: &str = "https://feeds.bbci.co.uk/news/rss.xml";
 
-#[derive(Deserialize)]
-struct RssFeed {
-    #[allow(dead_code)]
-    title: Option<String>,
-}
+pub struct BbcNewsProvider;
 
-#[derive(Deserialize)]
-struct RssItem {
-    title: Option<String>,
-    link: Option<String>,
-    description: Option<String>,
-    thumbnail: Option<String>,
+impl BbcNewsProvider {
+    fn feed() -> RssProvider {
+        RssProvider::new("bbc-news", "news", "BBC News", FEED_URL).with_max_description_chars(150)
+    }
 }
 
 #[async_trait::async_trait]
@@ -37,40 +23,7 @@ impl ContentProvider for BbcNewsProvider {
         "news"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
-        let rss_url = "https://feeds.bbci.co.uk/news/rss.xml";
-        let url = format!("https://api.rss2json.com/v1/api.json?rss_url={}", rss_url);
-
-        let response: Rss2Json = match fetch_json(client, &url).await {
-            Ok(r) => r,
-            Err(_) => return vec![],
-        };
-
-        let items_vec = match response.items {
-            Some(i) => i,
-            None => return vec![],
-        };
-
-        let items: Vec<FetchedItem> = items_vec
-            .into_iter()
-            .take(8)
-            .filter_map(|item| {
-                let title = item.title?;
-                let link = item.link?;
-                let desc = item.description.map(|d| strip_html(&d).chars().take(150).collect());
-                
-                Some(FetchedItem {
-                    source: "BBC News".into(),
-                    category: "news".into(),
-                    title,
-                    url: link,
-                    thumbnail_url: item.thumbnail,
-                    thumbnail_data: None,
-                    description: desc,
-                })
-            })
-            .collect();
-
-        items
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        Self::feed().fetch(client).await
     }
 }
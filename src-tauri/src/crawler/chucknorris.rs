@@ -1,4 +1,6 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use crate::net::HttpService;
 use serde::Deserialize;
 
 pub struct ChuckNorrisProvider;
@@ -21,29 +23,36 @@ impl ContentProvider for ChuckNorrisProvider {
         "joke"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let url = "https://api.chucknorris.io/jokes/random";
         let mut items = Vec::new();
+        let mut last_error = FetchError::Empty;
 
         for _ in 0..3 {
             let response = match client
                 .get(url)
                 .header("Accept", "application/json")
-                .header("User-Agent", "cazzmachine/0.2.0")
                 .send()
                 .await
             {
                 Ok(r) => r,
-                Err(_) => continue,
+                Err(e) => {
+                    last_error = e.into();
+                    continue;
+                }
             };
 
             if !response.status().is_success() {
+                last_error = FetchError::from_response(&response);
                 continue;
             }
 
             let joke: ChuckJoke = match response.json().await {
                 Ok(j) => j,
-                Err(_) => continue,
+                Err(e) => {
+                    last_error = e.into();
+                    continue;
+                }
             };
 
             items.push(FetchedItem {
@@ -54,9 +63,13 @@ impl ContentProvider for ChuckNorrisProvider {
                 thumbnail_url: None,
                 thumbnail_data: None,
                 description: Some(joke.value),
+                metadata: Default::default(),
             });
         }
 
-        items
+        if items.is_empty() {
+            return Err(last_error);
+        }
+        Ok(items)
     }
 }
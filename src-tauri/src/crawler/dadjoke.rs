@@ -1,4 +1,7 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use crate::net::HttpService;
+use super::util::non_empty;
 use serde::Deserialize;
 
 pub struct DadJokeProvider;
@@ -24,30 +27,22 @@ impl ContentProvider for DadJokeProvider {
         "joke"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let topics = ["work", "computer", "office", "coffee", "cat", "dog", "food", "money"];
         let topic = topics[rand::random::<usize>() % topics.len()];
         let url = format!("https://icanhazdadjoke.com/search?term={}&limit=5", topic);
 
-        let response = match client
+        let response = client
             .get(&url)
             .header("Accept", "application/json")
-            .header("User-Agent", "cazzmachine/0.1.0")
             .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(_) => return vec![],
-        };
+            .await?;
 
         if !response.status().is_success() {
-            return vec![];
+            return Err(FetchError::from_response(&response));
         }
 
-        let search: DadJokeSearchResponse = match response.json().await {
-            Ok(s) => s,
-            Err(_) => return vec![],
-        };
+        let search: DadJokeSearchResponse = response.json().await?;
 
         let items: Vec<FetchedItem> = search
             .results
@@ -60,9 +55,10 @@ impl ContentProvider for DadJokeProvider {
                 thumbnail_url: None,
                 thumbnail_data: None,
                 description: Some(joke.joke),
+                metadata: Default::default(),
             })
             .collect();
 
-        items
+        non_empty(items)
     }
 }
@@ -0,0 +1,251 @@
+use crate::db::models::CrawlItem;
+use crate::images::ImageData;
+
+/// Number of MinHash permutations; the Jaccard estimate is in steps of 1/64.
+pub const MINHASH_SIZE: usize = 64;
+
+/// Estimated title Jaccard similarity at or above which two items are the
+/// same story.
+pub const TITLE_THRESHOLD: f64 = 0.7;
+
+/// Titles shorter than this (after normalising) are too generic to compare;
+/// "me irl" is not a story.
+const MIN_TITLE_CHARS: usize = 24;
+
+/// Differing bits out of 64 at or below which two thumbnails are the same
+/// picture.
+pub const IMAGE_MAX_DISTANCE: u32 = 5;
+
+/// How far back the crawler looks for an earlier copy of an item.
+pub const WINDOW_DAYS: i64 = 3;
+
+/// Why an item was taken for a copy of an earlier one, as stored in
+/// `item_duplicates.reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateReason {
+    Url,
+    Title,
+    Image,
+}
+
+impl DuplicateReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DuplicateReason::Url => "url",
+            DuplicateReason::Title => "title",
+            DuplicateReason::Image => "image",
+        }
+    }
+}
+
+/// What an item is compared by. Computed before the item is stored, since
+/// decoding the thumbnail is too slow to do under the database lock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    pub normalized_url: String,
+    pub title_minhash: Option<Vec<u32>>,
+    pub image_dhash: Option<u64>,
+}
+
+impl Fingerprint {
+    pub fn of(item: &CrawlItem, thumbnail: Option<&ImageData>) -> Self {
+        Self {
+            normalized_url: normalize_url(&item.url),
+            title_minhash: title_minhash(&item.title),
+            image_dhash: thumbnail.and_then(|image| dhash(&image.bytes)),
+        }
+    }
+
+    /// Like [`Fingerprint::of`], but decodes the thumbnail on the blocking
+    /// pool so concurrent fetches keep going.
+    pub async fn compute(item: &CrawlItem, thumbnail: Option<&ImageData>) -> Self {
+        let image_dhash = match thumbnail {
+            Some(image) => {
+                let bytes = image.bytes.clone();
+                tokio::task::spawn_blocking(move || dhash(&bytes)).await.ok().flatten()
+            }
+            None => None,
+        };
+        Self {
+            normalized_url: normalize_url(&item.url),
+            title_minhash: title_minhash(&item.title),
+            image_dhash,
+        }
+    }
+
+    /// How `self` matches `earlier`, strongest signal first, with the
+    /// similarity in 0..=1.
+    pub fn matches(&self, earlier: &Fingerprint) -> Option<(DuplicateReason, f64)> {
+        if self.normalized_url == earlier.normalized_url {
+            return Some((DuplicateReason::Url, 1.0));
+        }
+        if let (Some(a), Some(b)) = (&self.title_minhash, &earlier.title_minhash) {
+            let similarity = minhash_similarity(a, b);
+            if similarity >= TITLE_THRESHOLD {
+                return Some((DuplicateReason::Title, similarity));
+            }
+        }
+        if let (Some(a), Some(b)) = (self.image_dhash, earlier.image_dhash) {
+            let distance = (a ^ b).count_ones();
+            if distance <= IMAGE_MAX_DISTANCE {
+                return Some((DuplicateReason::Image, 1.0 - distance as f64 / 64.0));
+            }
+        }
+        None
+    }
+}
+
+/// The earliest candidate `fingerprint` duplicates, with why and how closely.
+pub fn find_canonical<'a>(
+    fingerprint: &Fingerprint,
+    candidates: &'a [(String, Fingerprint)],
+) -> Option<(&'a str, DuplicateReason, f64)> {
+    candidates.iter().find_map(|(id, earlier)| {
+        fingerprint
+            .matches(earlier)
+            .map(|(reason, similarity)| (id.as_str(), reason, similarity))
+    })
+}
+
+/// Comparison key for a URL: its canonical form (see `url::canonicalize`),
+/// further relaxed in ways that never tell two items apart: `http` and
+/// `https`, with or without `www.` and a trailing slash, in any parameter
+/// order.
+pub fn normalize_url(url: &str) -> String {
+    let canonical = super::url::canonicalize(url);
+    let (rest, fragment) = match canonical.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (canonical.as_str(), None),
+    };
+    let rest = rest
+        .strip_prefix("https://")
+        .or_else(|| rest.strip_prefix("http://"))
+        .unwrap_or(rest);
+    let rest = rest.strip_prefix("www.").unwrap_or(rest);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let mut params: Vec<&str> = query.split('&').filter(|p| !p.is_empty()).collect();
+    params.sort_unstable();
+
+    let mut normalized = format!("https://{}", path.trim_end_matches('/'));
+    if !params.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        normalized.push('#');
+        normalized.push_str(fragment);
+    }
+    normalized
+}
+
+/// Lowercase alphanumerics with single spaces, so punctuation and casing
+/// don't count as differences.
+fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// MinHash signature over the title's character 4-shingles, or `None` for
+/// titles too short to tell stories apart.
+pub fn title_minhash(title: &str) -> Option<Vec<u32>> {
+    let title: Vec<char> = normalize_title(title).chars().collect();
+    if title.len() < MIN_TITLE_CHARS {
+        return None;
+    }
+
+    let mut signature = vec![u32::MAX; MINHASH_SIZE];
+    for shingle in title.windows(4) {
+        let base = fnv1a(shingle.iter().collect::<String>().as_bytes());
+        for (i, slot) in signature.iter_mut().enumerate() {
+            let hash = splitmix64(base ^ (i as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)) as u32;
+            *slot = (*slot).min(hash);
+        }
+    }
+    Some(signature)
+}
+
+/// Share of equal slots, an estimate of the shingle sets' Jaccard similarity.
+pub fn minhash_similarity(a: &[u32], b: &[u32]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let equal = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    equal as f64 / a.len() as f64
+}
+
+// Signatures are persisted, so these must never change.
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Difference hash of an image: shrink to 9x8 greyscale and record whether
+/// each pixel is darker than its right neighbour. Flat images, whose hash
+/// says nothing, give `None`.
+pub fn dhash(bytes: &[u8]) -> Option<u64> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let small = image.resize_exact(9, 8, image::imageops::FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    (4..=60).contains(&hash.count_ones()).then_some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://www.reddit.com/r/memes/comments/abc/title/"),
+            normalize_url("https://old.reddit.com/r/memes/comments/abc/title")
+        );
+        assert_eq!(
+            normalize_url("HTTP://www.Example.com/story/?utm_source=x&id=3&fbclid=y&a=1"),
+            "https://example.com/story?a=1&id=3"
+        );
+        assert_eq!(
+            normalize_url("https://www.google.com/url?q=x&url=https%3A%2F%2Fexample.com%2Fstory%3Futm_medium%3Dfeed"),
+            "https://example.com/story"
+        );
+        assert_ne!(normalize_url("https://example.com/a?id=1"), normalize_url("https://example.com/a?id=2"));
+        assert_ne!(
+            normalize_url("https://example.com/joke#3f2a"),
+            normalize_url("https://example.com/joke#9b1c"),
+            "fragments can identify items"
+        );
+    }
+
+    #[test]
+    fn test_title_minhash_matches_rewordings_only() {
+        let a = title_minhash("Scientists discover a new species of deep sea octopus").unwrap();
+        let b = title_minhash("Scientists discover new species of deep-sea octopus!").unwrap();
+        let c = title_minhash("Local council approves budget for new cycling lanes").unwrap();
+        assert!(minhash_similarity(&a, &b) >= TITLE_THRESHOLD);
+        assert!(minhash_similarity(&a, &c) < 0.2);
+        assert_eq!(title_minhash("me irl"), None);
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Why a provider fetch produced no items.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FetchError {
+    /// Connection, DNS, TLS or timeout failure before a response arrived.
+    Network { message: String },
+    /// The server answered with a non-success status.
+    HttpStatus { status: u16 },
+    /// The server answered 429, optionally telling us when to come back.
+    RateLimited { retry_after_secs: Option<u64> },
+    /// The response body did not match the expected schema.
+    Decode { message: String },
+    /// The request succeeded but there was nothing usable in it.
+    Empty,
+    /// The provider did not finish within the crawl executor's time budget.
+    Timeout { after_secs: u64 },
+}
+
+impl FetchError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FetchError::Network { .. } => "network",
+            FetchError::HttpStatus { .. } => "http_status",
+            FetchError::RateLimited { .. } => "rate_limited",
+            FetchError::Decode { .. } => "decode",
+            FetchError::Empty => "empty",
+            FetchError::Timeout { .. } => "timeout",
+        }
+    }
+
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            FetchError::HttpStatus { status } => Some(*status),
+            FetchError::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+
+    /// Diagnostic severity: an empty result is worth a warning, anything else is an error.
+    pub fn severity(&self) -> &'static str {
+        match self {
+            FetchError::Empty => "warn",
+            _ => "error",
+        }
+    }
+
+    /// Maps a non-success response to the matching error, reading `Retry-After` on 429.
+    pub fn from_response(response: &reqwest::Response) -> Self {
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after_secs = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok());
+            FetchError::RateLimited { retry_after_secs }
+        } else {
+            FetchError::HttpStatus {
+                status: status.as_u16(),
+            }
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Network { message } => write!(f, "network error: {}", message),
+            FetchError::HttpStatus { status } => write!(f, "HTTP status {}", status),
+            FetchError::RateLimited {
+                retry_after_secs: Some(secs),
+            } => write!(f, "rate limited, retry after {}s", secs),
+            FetchError::RateLimited {
+                retry_after_secs: None,
+            } => write!(f, "rate limited"),
+            FetchError::Decode { message } => write!(f, "decode error: {}", message),
+            FetchError::Empty => write!(f, "no items"),
+            FetchError::Timeout { after_secs } => write!(f, "timed out after {}s", after_secs),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            FetchError::Decode {
+                message: e.to_string(),
+            }
+        } else if let Some(status) = e.status() {
+            FetchError::HttpStatus {
+                status: status.as_u16(),
+            }
+        } else {
+            FetchError::Network {
+                message: e.to_string(),
+            }
+        }
+    }
+}
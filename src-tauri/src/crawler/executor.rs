@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+
+use super::provider::ContentProvider;
+use crate::db::Database;
+use crate::net::HttpService;
+
+/// Time budget for a single provider, thumbnails included.
+pub const DEFAULT_PROVIDER_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Runs one crawl cycle's providers concurrently.
+///
+/// At most `concurrency` providers are fetched at once and each one is cut
+/// off after `provider_timeout`, so a slow host only costs its own slot.
+pub struct CrawlExecutor {
+    concurrency: usize,
+    provider_timeout: Duration,
+}
+
+impl CrawlExecutor {
+    pub fn new(concurrency: usize, provider_timeout: Duration) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            provider_timeout,
+        }
+    }
+
+    /// Executor sized from the current doomscrolling thread count.
+    pub fn from_settings() -> Self {
+        Self::new(super::concurrency_limit(), DEFAULT_PROVIDER_TIMEOUT)
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Crawls every provider and returns the total number of new items.
+    pub async fn run(
+        &self,
+        db: &Database,
+        client: &HttpService,
+        providers: Vec<&dyn ContentProvider>,
+    ) -> u32 {
+        stream::iter(providers)
+            .map(|provider| super::crawl_provider(db, client, provider, self.provider_timeout))
+            .buffer_unordered(self.concurrency)
+            .fold(0u32, |total, added| async move { total + added })
+            .await
+    }
+}
@@ -0,0 +1,124 @@
+use regex::{Regex, RegexBuilder};
+
+use super::provider::FetchedItem;
+use crate::db::models::FilterRule;
+use crate::db::Database;
+
+/// Kinds of mute rule, as stored in `filter_rules.kind`.
+pub const FILTER_KINDS: [&str; 4] = ["keyword", "regex", "domain", "subreddit"];
+
+enum Matcher {
+    /// Lowercased; matched case-insensitively against title and description.
+    Keyword(String),
+    Regex(Regex),
+    /// Lowercased host; also matches its subdomains.
+    Domain(String),
+    /// Lowercased subreddit name without the `r/` prefix.
+    Subreddit(String),
+}
+
+impl Matcher {
+    fn compile(kind: &str, pattern: &str) -> Result<Self, String> {
+        match kind {
+            "keyword" => Ok(Matcher::Keyword(pattern.to_lowercase())),
+            "regex" => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| format!("Invalid regex: {}", e)),
+            "domain" => Ok(Matcher::Domain(pattern.to_lowercase())),
+            "subreddit" => Ok(Matcher::Subreddit(pattern.to_lowercase())),
+            _ => Err(format!("Unknown filter kind '{}'", kind)),
+        }
+    }
+
+    fn matches(&self, item: &FetchedItem) -> bool {
+        match self {
+            Matcher::Keyword(keyword) => text_fields(item).any(|t| t.to_lowercase().contains(keyword)),
+            Matcher::Regex(regex) => text_fields(item).any(|t| regex.is_match(t)),
+            Matcher::Domain(domain) => [Some(item.url.as_str()), item.thumbnail_url.as_deref()]
+                .into_iter()
+                .flatten()
+                .filter_map(host)
+                .any(|h| {
+                    let h = h.to_lowercase();
+                    h == *domain || h.ends_with(&format!(".{}", domain))
+                }),
+            Matcher::Subreddit(name) => {
+                let source = item.source.to_lowercase();
+                source.strip_prefix("r/") == Some(name.as_str())
+                    || item.url.to_lowercase().contains(&format!("/r/{}/", name))
+            }
+        }
+    }
+}
+
+fn text_fields(item: &FetchedItem) -> impl Iterator<Item = &str> {
+    [Some(item.title.as_str()), item.description.as_deref()]
+        .into_iter()
+        .flatten()
+}
+
+fn host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Checks a rule definition and returns its pattern in canonical form.
+pub fn normalize(kind: &str, pattern: &str) -> Result<String, String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("Filter pattern cannot be empty".to_string());
+    }
+    let pattern = match kind {
+        "domain" => pattern
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.")
+            .trim_end_matches('/')
+            .to_string(),
+        "subreddit" => pattern
+            .trim_start_matches('/')
+            .trim_start_matches("r/")
+            .trim_end_matches('/')
+            .to_string(),
+        _ => pattern.to_string(),
+    };
+    Matcher::compile(kind, &pattern)?;
+    Ok(pattern)
+}
+
+/// The enabled mute rules, compiled once per crawl.
+pub struct MuteList {
+    rules: Vec<(FilterRule, Matcher)>,
+}
+
+impl MuteList {
+    /// Compiles the enabled rules; rules that no longer compile are skipped.
+    pub fn new(rules: Vec<FilterRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| Matcher::compile(&r.kind, &r.pattern).ok().map(|m| (r, m)))
+            .collect();
+        Self { rules }
+    }
+
+    pub fn load(db: &Database) -> Self {
+        Self::new(db.list_filter_rules().unwrap_or_default())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// First rule that mutes `item`, if any.
+    pub fn matching_rule(&self, item: &FetchedItem) -> Option<&FilterRule> {
+        self.rules
+            .iter()
+            .find(|(_, matcher)| matcher.matches(item))
+            .map(|(rule, _)| rule)
+    }
+}
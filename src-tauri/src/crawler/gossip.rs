@@ -1,4 +1,9 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use super::reddit::RedditPostMeta;
+use super::util::non_empty;
+use crate::net::{HostLimit, HttpService};
+use serde::Deserialize;
 
 pub struct GossipProvider;
 
@@ -20,20 +25,20 @@ impl ContentProvider for GossipProvider {
         "gossip"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        super::reddit::rate_limits()
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let feed_url = "https://www.reddit.com/r/popculturechat/hot.json";
 
-        let listing: serde_json::Value = match super::util::fetch_json(client, feed_url).await {
-            Ok(v) => v,
-            Err(_) => return vec![],
-        };
+        let listing: serde_json::Value = super::util::fetch_json(client, feed_url).await?;
 
-        let children = match listing["data"]["children"].as_array() {
-            Some(c) => c,
-            None => {
-                return vec![];
-            }
-        };
+        let children = listing["data"]["children"]
+            .as_array()
+            .ok_or_else(|| FetchError::Decode {
+                message: "listing has no data.children array".into(),
+            })?;
 
         let mut items = Vec::new();
         for child in children.iter().take(6) {
@@ -58,23 +63,19 @@ impl ContentProvider for GossipProvider {
                 get_preview_image_url(&data["preview"])
             };
 
-            let thumbnail_data = if let Some(ref url) = thumb {
-                super::util::download_image(client, url).await
-            } else {
-                None
-            };
-
             items.push(FetchedItem {
                 source: "r/popculturechat".into(),
                 category: "gossip".into(),
                 title,
                 url: format!("https://reddit.com{}", permalink),
                 thumbnail_url: thumb,
-                thumbnail_data,
+                thumbnail_data: None,
                 description: None,
+                metadata: RedditPostMeta::deserialize(data).unwrap_or_default().into_metadata(),
             });
         }
 
-        items
+        super::util::download_thumbnails(client, &mut items).await;
+        non_empty(items)
     }
 }
@@ -1,4 +1,8 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use super::util::{local_timestamp, non_empty, url_domain};
+use crate::db::models::ItemMetadata;
+use crate::net::HttpService;
 use serde::Deserialize;
 
 pub struct HackerNewsProvider;
@@ -9,6 +13,15 @@ struct HnItem {
     id: i64,
     title: String,
     url: Option<String>,
+    #[serde(default)]
+    score: Option<i64>,
+    /// Total comment count.
+    #[serde(default)]
+    descendants: Option<i64>,
+    #[serde(default)]
+    by: Option<String>,
+    #[serde(default)]
+    time: Option<i64>,
 }
 
 #[async_trait::async_trait]
@@ -21,21 +34,19 @@ impl ContentProvider for HackerNewsProvider {
         "news"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let ids_url = "https://hacker-news.firebaseio.com/v0/topstories.json";
         
-        let ids: Vec<i64> = match client
+        let response = client
             .get(ids_url)
-            .header("User-Agent", "cazzmachine/0.2.0")
             .send()
-            .await
-        {
-            Ok(r) => match r.json().await {
-                Ok(ids) => ids,
-                Err(_) => return vec![],
-            },
-            Err(_) => return vec![],
-        };
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(FetchError::from_response(&response));
+        }
+
+        let ids: Vec<i64> = response.json().await?;
 
         let mut items = Vec::new();
         
@@ -44,7 +55,6 @@ impl ContentProvider for HackerNewsProvider {
             
             let item: HnItem = match client
                 .get(&item_url)
-                .header("User-Agent", "cazzmachine/0.2.0")
                 .send()
                 .await
             {
@@ -56,11 +66,17 @@ impl ContentProvider for HackerNewsProvider {
             };
 
             let domain = item.url
-                .as_ref()
-                .and_then(|u| u.split("://").nth(1))
-                .and_then(|u| u.split('/').next())
-                .unwrap_or("news.ycombinator.com")
-                .to_string();
+                .as_deref()
+                .and_then(url_domain)
+                .unwrap_or_else(|| "news.ycombinator.com".to_string());
+            let metadata = ItemMetadata {
+                score: item.score,
+                comments: item.descendants,
+                author: item.by,
+                published_at: item.time.and_then(local_timestamp),
+                media_duration_secs: None,
+                domain: Some(domain.clone()),
+            };
 
             items.push(FetchedItem {
                 source: "HackerNews".into(),
@@ -70,9 +86,10 @@ impl ContentProvider for HackerNewsProvider {
                 thumbnail_url: None,
                 thumbnail_data: None,
                 description: Some(domain),
+                metadata,
             });
         }
 
-        items
+        non_empty(items)
     }
 }
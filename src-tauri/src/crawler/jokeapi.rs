@@ -1,4 +1,7 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use crate::net::HttpService;
+use super::util::non_empty;
 use serde::Deserialize;
 
 pub struct JokeApiProvider;
@@ -31,31 +34,25 @@ impl ContentProvider for JokeApiProvider {
         "joke"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let url = "https://v2.jokeapi.dev/joke/Any?type=single&amount=10&blacklistFlags=nsfw,religious,political,racist,sexist,explicit";
 
-        let response = match client
+        let response = client
             .get(url)
             .header("Accept", "application/json")
-            .header("User-Agent", "cazzmachine/0.2.0")
             .send()
-            .await
-        {
-            Ok(r) => r,
-            Err(_) => return vec![],
-        };
+            .await?;
 
         if !response.status().is_success() {
-            return vec![];
+            return Err(FetchError::from_response(&response));
         }
 
-        let data: JokeApiResponse = match response.json().await {
-            Ok(d) => d,
-            Err(_) => return vec![],
-        };
+        let data: JokeApiResponse = response.json().await?;
 
         if data.error {
-            return vec![];
+            return Err(FetchError::Decode {
+                message: "jokeapi reported an error".into(),
+            });
         }
 
         let items: Vec<FetchedItem> = data
@@ -76,10 +73,11 @@ impl ContentProvider for JokeApiProvider {
                     thumbnail_url: None,
                     thumbnail_data: None,
                     description: Some(title),
+                    metadata: Default::default(),
                 })
             })
             .collect();
 
-        items
+        non_empty(items)
     }
 }
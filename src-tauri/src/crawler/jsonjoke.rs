@@ -0,0 +1,94 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::error::FetchError;
+use super::provider::{ContentProvider, FetchedItem};
+use crate::net::HttpService;
+use super::util::{fetch_json, non_empty};
+
+/// Provider for an arbitrary JSON joke endpoint.
+///
+/// Understands the common shapes: a single joke object, an array of them, or
+/// an object wrapping them in `jokes` / `results`. A joke's text is taken
+/// from `joke`, `value`, `text`, or `setup` + `punchline` / `delivery`.
+pub struct JsonJokeProvider {
+    endpoint: String,
+    category: String,
+    source: String,
+}
+
+impl JsonJokeProvider {
+    pub fn new(endpoint: &str, category: &str, source: &str) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            category: category.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Jokes have no URL of their own, so each gets a fragment derived from
+    /// its text; that keeps the stable item id unique per joke.
+    fn joke_url(&self, text: &str) -> String {
+        let digest = hex::encode(Sha256::digest(text.as_bytes()));
+        format!("{}#{}", self.endpoint, &digest[..12])
+    }
+}
+
+fn joke_text(value: &Value) -> Option<String> {
+    let field = |name: &str| value.get(name).and_then(Value::as_str).map(str::trim);
+    if let Some(text) = field("joke").or_else(|| field("value")).or_else(|| field("text")) {
+        return Some(text.to_string());
+    }
+    let setup = field("setup")?;
+    let punchline = field("punchline").or_else(|| field("delivery"))?;
+    Some(format!("{} {}", setup, punchline))
+}
+
+fn joke_values(body: Value) -> Vec<Value> {
+    match body {
+        Value::Array(values) => values,
+        Value::Object(mut map) => {
+            for key in ["jokes", "results"] {
+                if let Some(Value::Array(values)) = map.remove(key) {
+                    return values;
+                }
+            }
+            vec![Value::Object(map)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[async_trait::async_trait]
+impl ContentProvider for JsonJokeProvider {
+    fn name(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn category(&self) -> &str {
+        &self.category
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        let body: Value = fetch_json(client, &self.endpoint).await?;
+
+        let items: Vec<FetchedItem> = joke_values(body)
+            .iter()
+            .filter_map(joke_text)
+            .filter(|text| !text.is_empty())
+            .take(8)
+            .map(|text| FetchedItem {
+                source: self.source.clone(),
+                category: self.category.clone(),
+                title: text.clone(),
+                url: self.joke_url(&text),
+                thumbnail_url: None,
+                thumbnail_data: None,
+                description: Some(text),
+                metadata: Default::default(),
+            })
+            .collect();
+
+        non_empty(items)
+    }
+}
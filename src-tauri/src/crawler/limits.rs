@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+
+use super::provider::ContentProvider;
+use crate::net::{HostLimit, RateLimit};
+
+/// Bounds for an overridden budget; a limit of zero would defer a provider
+/// for good.
+const MAX_BURST: u32 = 100;
+const MAX_PER_MINUTE: u32 = 600;
+
+/// User overrides of the request budgets providers declare, keyed by
+/// provider name and then host, e.g. `{"gossip": {"reddit.com": {"burst": 2,
+/// "per_minute": 5}}}`. Hosts a provider doesn't declare are added to its
+/// budgets. Providers sharing a host share its bucket, which follows the
+/// limit of whichever of them was crawled last.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RateLimitOverrides(pub BTreeMap<String, BTreeMap<String, RateLimit>>);
+
+static CURRENT: OnceLock<RwLock<RateLimitOverrides>> = OnceLock::new();
+
+fn current_lock() -> &'static RwLock<RateLimitOverrides> {
+    CURRENT.get_or_init(|| RwLock::new(RateLimitOverrides::default()))
+}
+
+impl RateLimitOverrides {
+    /// The overrides currently in effect, mirrored from settings.
+    pub fn current() -> Self {
+        current_lock().read().unwrap().clone()
+    }
+
+    pub fn apply(&self) {
+        *current_lock().write().unwrap() = self.clone();
+    }
+
+    /// Keeps every limit within bounds, lowercases hosts and drops empty
+    /// names.
+    pub fn clamped(self) -> Self {
+        let overrides = self
+            .0
+            .into_iter()
+            .map(|(provider, hosts)| {
+                let hosts: BTreeMap<String, RateLimit> = hosts
+                    .into_iter()
+                    .map(|(host, limit)| {
+                        let limit = RateLimit {
+                            burst: limit.burst.clamp(1, MAX_BURST),
+                            per_minute: limit.per_minute.clamp(1, MAX_PER_MINUTE),
+                        };
+                        (host.trim().to_lowercase(), limit)
+                    })
+                    .filter(|(host, _)| !host.is_empty())
+                    .collect();
+                (provider.trim().to_string(), hosts)
+            })
+            .filter(|(provider, hosts)| !provider.is_empty() && !hosts.is_empty())
+            .collect();
+        Self(overrides)
+    }
+
+    /// `provider`'s budgets: the ones it declares, with overridden hosts
+    /// replaced, plus any hosts only the overrides name.
+    pub fn limits_for(&self, provider: &dyn ContentProvider) -> Vec<HostLimit> {
+        let mut limits = provider.rate_limits();
+        let Some(overrides) = self.0.get(provider.name()) else {
+            return limits;
+        };
+        for (host, limit) in overrides {
+            match limits.iter_mut().find(|l| l.host == *host) {
+                Some(existing) => existing.limit = *limit,
+                None => limits.push(HostLimit::new(host, *limit)),
+            }
+        }
+        limits
+    }
+}
@@ -1,4 +1,8 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use crate::net::{HostLimit, HttpService};
+use super::reddit::RedditPostMeta;
+use super::util::non_empty;
 use serde::Deserialize;
 
 pub struct RedditMemeProvider;
@@ -30,6 +34,8 @@ struct RedditPost {
     over_18: bool,
     #[serde(default)]
     stickied: bool,
+    #[serde(flatten)]
+    meta: RedditPostMeta,
 }
 
 #[async_trait::async_trait]
@@ -42,15 +48,16 @@ impl ContentProvider for RedditMemeProvider {
         "meme"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        super::reddit::rate_limits()
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let subreddits = ["dankmemes", "me_irl", "funny", "wholesomememes"];
         let sub = subreddits[rand::random::<usize>() % subreddits.len()];
         let url = format!("https://www.reddit.com/r/{}/hot.json?limit=10", sub);
 
-        let listing: RedditListing = match super::util::fetch_json(client, &url).await {
-            Ok(l) => l,
-            Err(_) => return vec![],
-        };
+        let listing: RedditListing = super::util::fetch_json(client, &url).await?;
 
         let mut items = Vec::new();
         for c in listing.data.children.into_iter().take(6) {
@@ -64,23 +71,19 @@ impl ContentProvider for RedditMemeProvider {
                 None
             };
 
-            let thumbnail_data = if let Some(ref url) = image_url {
-                super::util::download_image(client, url).await
-            } else {
-                None
-            };
-
             items.push(FetchedItem {
                 source: format!("r/{}", sub),
                 category: "meme".into(),
                 title: post.title,
                 url: format!("https://reddit.com{}", post.permalink),
                 thumbnail_url: image_url,
-                thumbnail_data,
+                thumbnail_data: None,
                 description: None,
+                metadata: post.meta.into_metadata(),
             });
         }
 
-        items
+        super::util::download_thumbnails(client, &mut items).await;
+        non_empty(items)
     }
 }
@@ -1,3 +1,4 @@
+pub mod error;
 pub mod provider;
 pub mod reddit;
 pub mod dadjoke;
@@ -11,13 +12,210 @@ pub mod uselessfacts;
 pub mod chucknorris;
 pub mod hackernews;
 pub mod bbcnews;
+pub mod rss;
+pub mod jsonjoke;
+pub mod sources;
+pub mod filters;
+pub mod dedupe;
+pub mod limits;
+pub mod url;
+pub mod registry;
+pub mod executor;
+
+use std::time::{Duration, Instant};
 
 use crate::commands::{THROTTLE_LEVEL, THREAD_COUNT};
+use crate::db::models::{InsertOutcome, ProviderRun};
+use crate::db::Database;
+use crate::net::{HostLimit, HttpService};
+use dedupe::Fingerprint;
+use error::FetchError;
+use executor::CrawlExecutor;
+use filters::MuteList;
+use limits::RateLimitOverrides;
+use provider::ContentProvider;
+use registry::ProviderRegistry;
 
 pub fn providers_per_cycle() -> usize {
     let level = THROTTLE_LEVEL.load(std::sync::atomic::Ordering::Relaxed);
     let thread_count = THREAD_COUNT.load(std::sync::atomic::Ordering::Relaxed) as usize;
     let count = 2 + ((level as usize - 1) * 4) / 8;
-    let scaled = count * thread_count.div_ceil(4);
-    scaled.min(13)
+    // The registry clamps this to the number of registered providers
+    count * thread_count.div_ceil(4)
+}
+
+/// Runs one crawl cycle over the next rotation of built-in and enabled
+/// user-defined providers and returns the number of new items. The image
+/// store is brought back under its size cap afterwards.
+pub async fn run_cycle(db: &Database, client: &HttpService) -> u32 {
+    let registry = ProviderRegistry::load(db);
+    let items_added = CrawlExecutor::from_settings()
+        .run(db, client, registry.rotation(providers_per_cycle()))
+        .await;
+    let _ = db.evict_images(crate::images::CACHE_MAX_BYTES);
+    items_added
+}
+
+/// Upper bound on simultaneous fetches, whatever the thread setting says.
+const MAX_CONCURRENCY: usize = 8;
+
+/// How many providers (and, within a provider, image downloads) may be in
+/// flight at once: two per doomscrolling thread, capped at `MAX_CONCURRENCY`.
+pub fn concurrency_limit() -> usize {
+    let thread_count = THREAD_COUNT.load(std::sync::atomic::Ordering::Relaxed) as usize;
+    (thread_count * 2).clamp(2, MAX_CONCURRENCY)
+}
+
+/// Fetches one provider, stores the items the mute list lets through that
+/// don't copy a recent item (copies are linked to the original) and records
+/// the invocation in `provider_runs` (plus a human-readable `diagnostic_logs`
+/// entry).
+/// A fetch still running after `timeout` is abandoned and recorded as a
+/// `timeout` failure.
+/// A provider whose request budget on one of its hosts is spent isn't fetched
+/// at all and is recorded as `deferred`.
+/// Returns the number of new items.
+pub async fn crawl_provider(
+    db: &Database,
+    client: &HttpService,
+    provider: &dyn ContentProvider,
+    timeout: Duration,
+) -> u32 {
+    let provider_name = provider.name();
+    let started_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
+
+    // The fetch's first request to each budgeted host is paid for up front,
+    // so providers sharing a host can't all pass this check on one token
+    let mut reservations = Vec::new();
+    let mut spent = Vec::new();
+    for HostLimit { host, limit } in RateLimitOverrides::current().limits_for(provider) {
+        client.set_rate_limit(&host, limit);
+        match client.try_reserve(&host) {
+            Some(reservation) => reservations.push(reservation),
+            None => spent.push(host),
+        }
+    }
+    if !spent.is_empty() {
+        let reason = format!("request budget for {} spent", spent.join(", "));
+        let _ = db.record_provider_run(&ProviderRun {
+            provider: provider_name.to_string(),
+            started_at,
+            duration_ms: 0,
+            outcome: "deferred".to_string(),
+            items_fetched: 0,
+            items_new: 0,
+            error: Some(reason.clone()),
+            status_code: None,
+        });
+        let _ = db.log_diagnostic_event(
+            "crawl_deferred",
+            "info",
+            &format!("{}: deferred, {}", provider_name, reason),
+            None,
+            None,
+        );
+        return 0;
+    }
+
+    let started = Instant::now();
+    let result = match tokio::time::timeout(timeout, provider.fetch(client)).await {
+        Ok(result) => result,
+        Err(_) => Err(FetchError::Timeout {
+            after_secs: timeout.as_secs(),
+        }),
+    };
+    drop(reservations);
+    let duration_ms = started.elapsed().as_millis() as i64;
+
+    let mut run = ProviderRun {
+        provider: provider_name.to_string(),
+        started_at,
+        duration_ms,
+        outcome: "success".to_string(),
+        items_fetched: 0,
+        items_new: 0,
+        error: None,
+        status_code: None,
+    };
+
+    let items = match result {
+        Ok(items) => items,
+        Err(e) => {
+            run.outcome = e.kind().to_string();
+            run.error = Some(e.to_string());
+            run.status_code = e.status_code();
+            let _ = db.record_provider_run(&run);
+            let _ = db.log_diagnostic_event(
+                "crawl_error",
+                e.severity(),
+                &format!("{}: {} ({}ms)", provider_name, e, duration_ms),
+                None,
+                None,
+            );
+            return 0;
+        }
+    };
+
+    run.items_fetched = items.len() as i64;
+    let mute_list = MuteList::load(db);
+    let mut items_added = 0u32;
+    for item in items {
+        if let Some(rule) = mute_list.matching_rule(&item) {
+            let _ = db.record_filter_hit(&rule.id);
+            let _ = db.log_diagnostic_event(
+                "item_filtered",
+                "info",
+                &format!(
+                    "{}: dropped \"{}\" ({} rule \"{}\")",
+                    provider_name, item.title, rule.kind, rule.pattern
+                ),
+                None,
+                None,
+            );
+            continue;
+        }
+
+        let (crawl_item, thumbnail) = item.into_crawl_item();
+        let fingerprint = Fingerprint::compute(&crawl_item, thumbnail.as_ref()).await;
+        match db.insert_item_deduped(&crawl_item, &fingerprint, thumbnail.as_ref()) {
+            Ok(InsertOutcome::Inserted) => items_added += 1,
+            Ok(InsertOutcome::AlreadyStored) => {}
+            Ok(InsertOutcome::Duplicate { canonical_id, reason }) => {
+                let _ = db.log_diagnostic_event(
+                    "item_duplicate",
+                    "info",
+                    &format!(
+                        "{}: linked \"{}\" to an earlier copy (same {})",
+                        provider_name, crawl_item.title, reason
+                    ),
+                    None,
+                    Some(&canonical_id),
+                );
+            }
+            Err(e) => {
+                let _ = db.log_diagnostic_event(
+                    "insert_error",
+                    "warn",
+                    &format!("Failed to insert item: {}", e),
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+    run.items_new = items_added as i64;
+
+    let _ = db.record_provider_run(&run);
+    let _ = db.log_diagnostic_event(
+        "crawl_success",
+        "info",
+        &format!(
+            "{}: fetched {} items, {} new ({}ms)",
+            provider_name, run.items_fetched, items_added, duration_ms
+        ),
+        None,
+        None,
+    );
+
+    items_added
 }
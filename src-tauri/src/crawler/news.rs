@@ -1,20 +1,16 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
-use super::util::{urlencoded, strip_html};
-use serde::Deserialize;
+use crate::net::HttpService;
+use super::rss::RssProvider;
 
-pub struct GoogleNewsRssProvider;
+const FEED_URL: &str = "https://news.google.com/rss?hl=en-US&gl=US&ceid=US:en";
 
-#[derive(Deserialize)]
-struct RssResponse {
-    items: Vec<RssItem>,
-}
+pub struct GoogleNewsRssProvider;
 
-#[derive(Deserialize)]
-struct RssItem {
-    title: String,
-    link: String,
-    #[serde(default)]
-    description: String,
+impl GoogleNewsRssProvider {
+    fn feed() -> RssProvider {
+        RssProvider::new("google-news", "news", "Google News", FEED_URL)
+    }
 }
 
 #[async_trait::async_trait]
@@ -27,40 +23,7 @@ impl ContentProvider for GoogleNewsRssProvider {
         "news"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
-        let rss_url = "https://news.google.com/rss?hl=en-US&gl=US&ceid=US:en";
-        let rss_to_json = format!(
-            "https://api.rss2json.com/v1/api.json?rss_url={}",
-            urlencoded(rss_url)
-        );
-
-        let rss: RssResponse = match super::util::fetch_json(client, &rss_to_json).await {
-            Ok(r) => r,
-            Err(_) => return vec![],
-        };
-
-        let items: Vec<FetchedItem> = rss.items
-            .into_iter()
-            .take(8)
-            .map(|item| {
-                let desc = if item.description.is_empty() {
-                    None
-                } else {
-                    Some(strip_html(&item.description).chars().take(200).collect())
-                };
-                FetchedItem {
-                    source: "Google News".into(),
-                    category: "news".into(),
-                    title: item.title,
-                    url: item.link,
-                    thumbnail_url: None,
-                    thumbnail_data: None,
-                    description: desc,
-                }
-            })
-            .collect();
-
-        items
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        Self::feed().fetch(client).await
     }
 }
-
@@ -1,38 +1,50 @@
-use crate::db::models::CrawlItem;
+use super::error::FetchError;
+use crate::db::models::{CrawlItem, ItemMetadata};
+use crate::images::ImageData;
+use crate::net::{HostLimit, HttpService};
 
+#[derive(Debug, Clone)]
 pub struct FetchedItem {
     pub source: String,
     pub category: String,
     pub title: String,
     pub url: String,
     pub thumbnail_url: Option<String>,
-    pub thumbnail_data: Option<String>,
+    /// The thumbnail as downloaded, if `thumbnail_url` was fetched.
+    pub thumbnail_data: Option<ImageData>,
     pub description: Option<String>,
+    pub metadata: ItemMetadata,
 }
 
 impl FetchedItem {
-    pub fn into_crawl_item(self) -> CrawlItem {
+    /// The item as stored, and its thumbnail, which the item references by
+    /// hash and which is stored separately.
+    pub fn into_crawl_item(self) -> (CrawlItem, Option<ImageData>) {
         let now = chrono::Local::now();
-        let id = generate_stable_id(&self.url);
-        CrawlItem {
+        let url = super::url::canonicalize(&self.url);
+        let id = generate_stable_id(&url);
+        let item = CrawlItem {
             id,
             source: self.source,
             category: self.category,
             title: self.title,
-            url: self.url,
+            url,
             thumbnail_url: self.thumbnail_url,
-            thumbnail_data: self.thumbnail_data,
+            thumbnail_hash: self.thumbnail_data.as_ref().map(ImageData::hash),
             description: self.description,
             fetched_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
             is_seen: false,
             is_saved: false,
             is_consumed: false,
             session_date: now.format("%Y-%m-%d").to_string(),
-        }
+            metadata: self.metadata,
+        };
+        (item, self.thumbnail_data)
     }
 }
 
-fn generate_stable_id(url: &str) -> String {
+/// Item id derived from its URL, so the same item always gets the same id.
+pub fn generate_stable_id(url: &str) -> String {
     // Use first 32 chars of SHA256 hash of URL as stable ID
     use sha2::{Sha256, Digest};
     let mut hasher = Sha256::new();
@@ -46,5 +58,12 @@ fn generate_stable_id(url: &str) -> String {
 pub trait ContentProvider: Send + Sync {
     fn name(&self) -> &str;
     fn category(&self) -> &str;
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem>;
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError>;
+
+    /// Request budgets on the hosts this provider fetches from. The crawler
+    /// defers the provider while any of them is spent. These are defaults;
+    /// the `rate_limits` setting can override them per provider.
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        Vec::new()
+    }
 }
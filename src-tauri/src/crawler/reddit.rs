@@ -1,4 +1,8 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use super::util::{local_timestamp, non_empty};
+use crate::db::models::ItemMetadata;
+use crate::net::{HostLimit, HttpService, RateLimit};
 use serde::Deserialize;
 
 fn is_image_url(url: &str) -> bool {
@@ -12,12 +16,30 @@ fn is_image_url(url: &str) -> bool {
         || url.contains("preview.redd.it")
 }
 
+/// Default budget for reddit.com's unauthenticated JSON listings, shared by
+/// every reddit-backed provider. Reddit answers 429 past about ten a minute.
+pub const API_LIMIT: RateLimit = RateLimit { burst: 4, per_minute: 10 };
+/// Default budget for thumbnails on redd.it's image hosts.
+pub const MEDIA_LIMIT: RateLimit = RateLimit { burst: 30, per_minute: 60 };
+
+/// Rate limits of the providers that read reddit listings.
+pub fn rate_limits() -> Vec<HostLimit> {
+    vec![HostLimit::new("reddit.com", API_LIMIT), HostLimit::new("redd.it", MEDIA_LIMIT)]
+}
+
 pub struct RedditProvider {
     subreddit: String,
     category: String,
 }
 
 impl RedditProvider {
+    pub fn new(subreddit: &str, category: &str) -> Self {
+        Self {
+            subreddit: subreddit.into(),
+            category: category.into(),
+        }
+    }
+
     pub fn memes() -> Self {
         Self {
             subreddit: "memes".into(),
@@ -73,6 +95,51 @@ struct RedditPost {
     is_self: bool,
     #[serde(default)]
     preview: serde_json::Value,
+    #[serde(flatten)]
+    meta: RedditPostMeta,
+}
+
+/// The engagement fields every post in a Reddit listing carries. Shared by
+/// all the Reddit-backed providers.
+#[derive(Debug, Default, Deserialize)]
+pub struct RedditPostMeta {
+    #[serde(default)]
+    score: Option<i64>,
+    #[serde(default)]
+    num_comments: Option<i64>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    created_utc: Option<f64>,
+    #[serde(default)]
+    domain: Option<String>,
+    #[serde(default)]
+    media: Option<RedditMedia>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RedditMedia {
+    #[serde(default)]
+    reddit_video: Option<RedditVideo>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RedditVideo {
+    #[serde(default)]
+    duration: Option<f64>,
+}
+
+impl RedditPostMeta {
+    pub fn into_metadata(self) -> ItemMetadata {
+        ItemMetadata {
+            score: self.score,
+            comments: self.num_comments,
+            author: self.author.filter(|a| a != "[deleted]"),
+            published_at: self.created_utc.and_then(|secs| local_timestamp(secs as i64)),
+            media_duration_secs: self.media.and_then(|m| m.reddit_video).and_then(|v| v.duration),
+            domain: self.domain,
+        }
+    }
 }
 
 fn get_preview_image_url(post: &RedditPost) -> Option<String> {
@@ -94,16 +161,17 @@ impl ContentProvider for RedditProvider {
         &self.category
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        rate_limits()
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let url = format!(
             "https://www.reddit.com/r/{}/hot.json?limit=10",
             self.subreddit
         );
 
-        let listing: RedditListing = match super::util::fetch_json(client, &url).await {
-            Ok(l) => l,
-            Err(_) => return vec![],
-        };
+        let listing: RedditListing = super::util::fetch_json(client, &url).await?;
 
         let mut items = Vec::new();
         for c in listing.data.children.into_iter().take(8) {
@@ -119,12 +187,6 @@ impl ContentProvider for RedditProvider {
                 get_preview_image_url(&post)
             };
 
-            let thumbnail_data = if let Some(ref url) = thumbnail {
-                super::util::download_image(client, url).await
-            } else {
-                None
-            };
-
             let description = if post.selftext.is_empty() {
                 None
             } else {
@@ -137,11 +199,13 @@ impl ContentProvider for RedditProvider {
                 title: post.title,
                 url: format!("https://reddit.com{}", post.permalink),
                 thumbnail_url: thumbnail,
-                thumbnail_data,
+                thumbnail_data: None,
                 description,
+                metadata: post.meta.into_metadata(),
             });
         }
 
-        items
+        super::util::download_thumbnails(client, &mut items).await;
+        non_empty(items)
     }
 }
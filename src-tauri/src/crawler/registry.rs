@@ -0,0 +1,100 @@
+use super::provider::ContentProvider;
+use crate::db::models::Source;
+use crate::db::Database;
+
+/// Owns every content provider known to the crawler.
+///
+/// Both crawl paths (the `trigger_crawl` command and the crawl scheduler) and
+/// provider status reporting go through the registry, so adding a provider
+/// only means registering it in `builtin()`. User-defined sources from the
+/// `sources` table are appended by `load()`.
+pub struct ProviderRegistry {
+    providers: Vec<Box<dyn ContentProvider>>,
+}
+
+impl ProviderRegistry {
+    pub fn builtin() -> Self {
+        let providers: Vec<Box<dyn ContentProvider>> = vec![
+            Box::new(super::reddit::RedditProvider::memes()),
+            Box::new(super::reddit::RedditProvider::dad_jokes()),
+            Box::new(super::reddit::RedditProvider::celebrity_gossip()),
+            Box::new(super::dadjoke::DadJokeProvider),
+            Box::new(super::meme::RedditMemeProvider),
+            Box::new(super::video::RedditVideoProvider),
+            Box::new(super::gossip::GossipProvider),
+            Box::new(super::news::GoogleNewsRssProvider),
+            Box::new(super::jokeapi::JokeApiProvider),
+            Box::new(super::uselessfacts::UselessFactsProvider),
+            Box::new(super::chucknorris::ChuckNorrisProvider),
+            Box::new(super::hackernews::HackerNewsProvider),
+            Box::new(super::bbcnews::BbcNewsProvider),
+        ];
+        Self { providers }
+    }
+
+    /// Built-in providers plus the given user-defined sources. Disabled
+    /// sources, invalid rows and names already taken are skipped.
+    pub fn with_sources(sources: &[Source]) -> Self {
+        let mut registry = Self::builtin();
+        for source in sources.iter().filter(|s| s.enabled) {
+            if let Some(provider) = super::sources::build_provider(source) {
+                if registry.get(provider.name()).is_none() {
+                    registry.providers.push(provider);
+                }
+            }
+        }
+        registry
+    }
+
+    /// Registry for a crawl: built-ins plus the enabled sources in `db`.
+    pub fn load(db: &Database) -> Self {
+        Self::with_sources(&db.list_sources().unwrap_or_default())
+    }
+
+    pub fn all(&self) -> &[Box<dyn ContentProvider>] {
+        &self.providers
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ContentProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+
+    pub fn by_category(&self, category: &str) -> Vec<&dyn ContentProvider> {
+        self.providers
+            .iter()
+            .filter(|p| p.category() == category)
+            .map(|p| p.as_ref())
+            .collect()
+    }
+
+    /// (name, category) pairs for every registered provider, in registration order.
+    pub fn descriptors(&self) -> Vec<(String, String)> {
+        self.providers
+            .iter()
+            .map(|p| (p.name().to_string(), p.category().to_string()))
+            .collect()
+    }
+
+    /// Picks the providers for one crawl cycle: `count` consecutive providers
+    /// starting from a random offset, wrapping around the registry.
+    pub fn rotation(&self, count: usize) -> Vec<&dyn ContentProvider> {
+        if self.providers.is_empty() {
+            return Vec::new();
+        }
+        let start = rand::random::<usize>() % self.providers.len();
+        (0..count.min(self.providers.len()))
+            .map(|i| self.providers[(start + i) % self.providers.len()].as_ref())
+            .collect()
+    }
+}
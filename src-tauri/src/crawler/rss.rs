@@ -0,0 +1,332 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use super::error::FetchError;
+use super::provider::{ContentProvider, FetchedItem};
+use super::util::{non_empty, strip_html, url_domain};
+use crate::db::models::ItemMetadata;
+use crate::net::HttpService;
+
+/// One `<item>` (RSS) or `<entry>` (Atom) of a feed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeedEntry {
+    pub title: String,
+    pub link: String,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    /// RFC 3339 timestamp from `pubDate` / `published` / `updated`.
+    pub published_at: Option<String>,
+    /// `dc:creator` / `author`, or the `name` of an Atom author.
+    pub author: Option<String>,
+}
+
+/// Text-bearing child elements of an entry we care about.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Title,
+    Link,
+    Description,
+    Content,
+    Published,
+    Updated,
+    Author,
+}
+
+impl Field {
+    fn from_element(qname: &[u8]) -> Option<Self> {
+        match qname {
+            b"title" => Some(Field::Title),
+            b"link" => Some(Field::Link),
+            b"description" | b"summary" => Some(Field::Description),
+            b"content" | b"content:encoded" => Some(Field::Content),
+            b"pubDate" | b"published" | b"dc:date" => Some(Field::Published),
+            b"updated" => Some(Field::Updated),
+            b"author" | b"dc:creator" => Some(Field::Author),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct EntryBuilder {
+    entry: FeedEntry,
+    content: Option<String>,
+    updated: Option<String>,
+}
+
+impl EntryBuilder {
+    fn set(&mut self, field: Field, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        match field {
+            Field::Title => self.entry.title = text,
+            Field::Link => {
+                if self.entry.link.is_empty() {
+                    self.entry.link = text;
+                }
+            }
+            Field::Description => self.entry.description = Some(text),
+            Field::Content => self.content = Some(text),
+            Field::Published => self.entry.published_at = parse_date(&text),
+            Field::Updated => self.updated = parse_date(&text),
+            Field::Author => self.entry.author = Some(text),
+        }
+    }
+
+    /// Handles attribute-only elements: Atom links, enclosures and Media RSS.
+    fn attributes(&mut self, e: &BytesStart) {
+        match e.name().as_ref() {
+            b"link" => {
+                let rel = attribute(e, "rel");
+                if matches!(rel.as_deref(), None | Some("alternate")) {
+                    if let Some(href) = attribute(e, "href") {
+                        self.entry.link = href;
+                    }
+                }
+            }
+            b"enclosure" => {
+                let is_image = attribute(e, "type").is_some_and(|t| t.starts_with("image/"));
+                if is_image {
+                    self.set_image(attribute(e, "url"));
+                }
+            }
+            b"media:thumbnail" => self.set_image(attribute(e, "url")),
+            b"media:content" => {
+                let is_image = attribute(e, "medium").as_deref() == Some("image")
+                    || attribute(e, "type").is_some_and(|t| t.starts_with("image/"));
+                if is_image {
+                    self.set_image(attribute(e, "url"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_image(&mut self, url: Option<String>) {
+        if self.entry.image_url.is_none() {
+            self.entry.image_url = url;
+        }
+    }
+
+    fn build(self) -> Option<FeedEntry> {
+        let mut entry = self.entry;
+        if entry.title.is_empty() || entry.link.is_empty() {
+            return None;
+        }
+        if entry.description.is_none() {
+            entry.description = self.content;
+        }
+        if entry.published_at.is_none() {
+            entry.published_at = self.updated;
+        }
+        Some(entry)
+    }
+}
+
+fn attribute(e: &BytesStart, name: &str) -> Option<String> {
+    e.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// RSS uses RFC 2822 dates, Atom uses RFC 3339; both come out as RFC 3339.
+fn parse_date(text: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc2822(text)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(text))
+        .ok()
+        .map(|d| d.to_rfc3339())
+}
+
+fn decode_error(e: impl std::fmt::Display) -> FetchError {
+    FetchError::Decode {
+        message: e.to_string(),
+    }
+}
+
+/// Parses an RSS 2.0, RSS 1.0 (RDF) or Atom document into its entries.
+///
+/// Entries without a title or link are skipped. A document whose root is
+/// not a feed is a decode error.
+pub fn parse_feed(xml: &str) -> Result<Vec<FeedEntry>, FetchError> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut is_feed = false;
+    let mut entries = Vec::new();
+    let mut current: Option<EntryBuilder> = None;
+    let mut field: Option<(Field, Vec<u8>)> = None;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event().map_err(decode_error)? {
+            Event::Start(e) => {
+                let name = e.name();
+                match name.as_ref() {
+                    b"rss" | b"feed" | b"rdf:RDF" => is_feed = true,
+                    b"item" | b"entry" => current = Some(EntryBuilder::default()),
+                    qname => {
+                        if let Some(entry) = current.as_mut() {
+                            entry.attributes(&e);
+                            if field.is_none() {
+                                if let Some(f) = Field::from_element(qname) {
+                                    field = Some((f, qname.to_vec()));
+                                    text.clear();
+                                }
+                            } else if qname == b"name" && matches!(field, Some((Field::Author, _))) {
+                                // Atom authors are a person construct; keep only
+                                // the name, not the email or uri
+                                field = Some((Field::Author, qname.to_vec()));
+                                text.clear();
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Empty(e) => {
+                if let Some(entry) = current.as_mut() {
+                    entry.attributes(&e);
+                }
+            }
+            Event::Text(t) if field.is_some() => {
+                let unescaped = t
+                    .unescape()
+                    .map(|s| s.into_owned())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&t).into_owned());
+                text.push_str(&unescaped);
+            }
+            Event::CData(c) if field.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&c));
+            }
+            Event::End(e) => {
+                let name = e.name();
+                match name.as_ref() {
+                    b"item" | b"entry" => {
+                        if let Some(entry) = current.take().and_then(EntryBuilder::build) {
+                            entries.push(entry);
+                        }
+                        field = None;
+                    }
+                    qname => {
+                        if let Some((f, open)) = field.as_ref() {
+                            if open.as_slice() == qname {
+                                let f = *f;
+                                field = None;
+                                if let Some(entry) = current.as_mut() {
+                                    entry.set(f, std::mem::take(&mut text).trim().to_string());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !is_feed {
+        return Err(decode_error("not an RSS or Atom feed"));
+    }
+    Ok(entries)
+}
+
+/// Downloads and parses a feed.
+pub async fn fetch_feed(client: &HttpService, url: &str) -> Result<Vec<FeedEntry>, FetchError> {
+    let response = client
+        .get(url)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(FetchError::from_response(&response));
+    }
+
+    let body = response.text().await?;
+    parse_feed(&body)
+}
+
+/// Generic provider for any RSS or Atom feed.
+pub struct RssProvider {
+    name: String,
+    category: String,
+    source: String,
+    feed_url: String,
+    max_items: usize,
+    max_description_chars: usize,
+}
+
+impl RssProvider {
+    pub fn new(name: &str, category: &str, source: &str, feed_url: &str) -> Self {
+        Self {
+            name: name.into(),
+            category: category.into(),
+            source: source.into(),
+            feed_url: feed_url.into(),
+            max_items: 8,
+            max_description_chars: 200,
+        }
+    }
+
+    pub fn with_max_description_chars(mut self, chars: usize) -> Self {
+        self.max_description_chars = chars;
+        self
+    }
+
+    pub fn feed_url(&self) -> &str {
+        &self.feed_url
+    }
+
+    fn to_item(&self, entry: FeedEntry) -> FetchedItem {
+        let description = entry
+            .description
+            .map(|d| strip_html(&d).trim().chars().take(self.max_description_chars).collect::<String>())
+            .filter(|d| !d.is_empty());
+
+        let metadata = ItemMetadata {
+            author: entry.author,
+            published_at: entry.published_at.as_deref().and_then(|d| {
+                chrono::DateTime::parse_from_rfc3339(d)
+                    .ok()
+                    .map(|d| d.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            }),
+            domain: url_domain(&entry.link),
+            ..Default::default()
+        };
+
+        FetchedItem {
+            source: self.source.clone(),
+            category: self.category.clone(),
+            title: entry.title,
+            url: entry.link,
+            thumbnail_url: entry.image_url,
+            thumbnail_data: None,
+            description,
+            metadata,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ContentProvider for RssProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn category(&self) -> &str {
+        &self.category
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        let entries = fetch_feed(client, &self.feed_url).await?;
+
+        let items: Vec<FetchedItem> = entries
+            .into_iter()
+            .take(self.max_items)
+            .map(|entry| self.to_item(entry))
+            .collect();
+
+        non_empty(items)
+    }
+}
@@ -0,0 +1,66 @@
+use super::jsonjoke::JsonJokeProvider;
+use super::provider::ContentProvider;
+use super::reddit::RedditProvider;
+use super::rss::RssProvider;
+use crate::db::models::Source;
+
+/// Kinds of user-defined source, as stored in `sources.kind`.
+pub const SOURCE_KINDS: [&str; 3] = ["subreddit", "feed", "json_joke"];
+
+/// Categories a source can be filed under.
+pub const CATEGORIES: [&str; 5] = ["meme", "joke", "news", "video", "gossip"];
+
+/// Checks a source definition and returns its target in canonical form:
+/// subreddits without the `r/` prefix, URLs trimmed. The canonical target
+/// doubles as the provider name.
+pub fn normalize(kind: &str, target: &str, category: &str) -> Result<String, String> {
+    if !CATEGORIES.contains(&category) {
+        return Err(format!("Unknown category '{}'", category));
+    }
+
+    let target = target.trim();
+    match kind {
+        "subreddit" => {
+            let name = target
+                .trim_start_matches('/')
+                .trim_start_matches("r/")
+                .trim_end_matches('/');
+            let valid = (2..=21).contains(&name.len())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if valid {
+                Ok(name.to_string())
+            } else {
+                Err(format!("'{}' is not a valid subreddit name", target))
+            }
+        }
+        "feed" | "json_joke" => {
+            if host(target).is_some() {
+                Ok(target.to_string())
+            } else {
+                Err(format!("'{}' is not an http(s) URL", target))
+            }
+        }
+        _ => Err(format!("Unknown source kind '{}'", kind)),
+    }
+}
+
+fn host(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Instantiates the provider for a stored source, or `None` if the row no
+/// longer describes a valid source.
+pub fn build_provider(source: &Source) -> Option<Box<dyn ContentProvider>> {
+    let target = normalize(&source.kind, &source.target, &source.category).ok()?;
+    let provider: Box<dyn ContentProvider> = match source.kind.as_str() {
+        "subreddit" => Box::new(RedditProvider::new(&target, &source.category)),
+        "feed" => Box::new(RssProvider::new(&target, &source.category, host(&target)?, &target)),
+        "json_joke" => Box::new(JsonJokeProvider::new(&target, &source.category, host(&target)?)),
+        _ => return None,
+    };
+    Some(provider)
+}
@@ -0,0 +1,158 @@
+use ::url::Url;
+
+/// Query parameters that only track where a click came from. A bare `ref`
+/// isn't one of them: GitHub and others use it to pick what's shown.
+const TRACKING_PARAMS: [&str; 11] = [
+    "fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid", "ref_src",
+    "at_medium", "at_campaign", "oc",
+];
+
+/// Alternative front ends of Reddit; items are stored under `reddit.com`,
+/// the host the Reddit providers build permalinks with.
+const REDDIT_HOSTS: [&str; 6] = [
+    "www.reddit.com",
+    "old.reddit.com",
+    "new.reddit.com",
+    "np.reddit.com",
+    "m.reddit.com",
+    "i.reddit.com",
+];
+
+/// The URL an item is stored and identified by.
+///
+/// Lowercases the host, follows redirector links that carry their target
+/// in the query (Google, Facebook, Reddit outbound), unifies Reddit hosts
+/// and drops `utm_*` and other tracking parameters. Everything else is left
+/// as the provider built it: other parameters (JokeAPI's `idRange`) and
+/// fragments (`jsonjoke`'s per-joke digests) can be what tells two items
+/// apart. Strings that don't parse as URLs are returned trimmed.
+pub fn canonicalize(url: &str) -> String {
+    let trimmed = url.trim();
+    let Ok(mut parsed) = Url::parse(trimmed) else {
+        return trimmed.to_string();
+    };
+    if let Some(target) = redirect_target(&parsed) {
+        return canonicalize(&target);
+    }
+
+    if parsed.host_str().is_some_and(|host| REDDIT_HOSTS.contains(&host)) {
+        let _ = parsed.set_host(Some("reddit.com"));
+    }
+
+    if let Some(query) = parsed.query() {
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty() && !is_tracking(pair.split('=').next().unwrap_or_default()))
+            .collect();
+        let kept = kept.join("&");
+        parsed.set_query((!kept.is_empty()).then_some(kept.as_str()));
+    }
+
+    parsed.to_string()
+}
+
+fn is_tracking(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+/// Destination of a known redirector link.
+fn redirect_target(url: &Url) -> Option<String> {
+    let host = url.host_str()?.trim_start_matches("www.");
+    let key = match host {
+        "google.com" | "news.google.com" if url.path() == "/url" => "url",
+        "l.facebook.com" | "lm.facebook.com" => "u",
+        "out.reddit.com" => "url",
+        _ => return None,
+    };
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, target)| target.into_owned())
+        .filter(|target| target.starts_with("http://") || target.starts_with("https://"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::canonicalize;
+
+    #[test]
+    fn test_canonicalize_provider_urls() {
+        let cases = [
+            // reddit, meme, video, gossip: permalinks are already canonical
+            (
+                "https://reddit.com/r/memes/comments/1abc/cat/",
+                "https://reddit.com/r/memes/comments/1abc/cat/",
+            ),
+            (
+                "https://www.reddit.com/r/memes/comments/1abc/cat/",
+                "https://reddit.com/r/memes/comments/1abc/cat/",
+            ),
+            (
+                "https://old.reddit.com/r/memes/comments/1abc/cat/?utm_source=share&utm_medium=web2x",
+                "https://reddit.com/r/memes/comments/1abc/cat/",
+            ),
+            (
+                "https://out.reddit.com/t3_1abc?url=https%3A%2F%2FExample.com%2Fstory%3Futm_campaign%3Dx&token=t",
+                "https://example.com/story",
+            ),
+            // hackernews: external links and self posts
+            (
+                "https://GitHub.com/rust-lang/rust?tab=readme&fbclid=IwAR0",
+                "https://github.com/rust-lang/rust?tab=readme",
+            ),
+            (
+                "https://github.com/rust-lang/rust/blob/README.md?ref=beta&ref_src=twsrc",
+                "https://github.com/rust-lang/rust/blob/README.md?ref=beta",
+            ),
+            (
+                "https://news.ycombinator.com/item?id=41000000",
+                "https://news.ycombinator.com/item?id=41000000",
+            ),
+            // bbc-news and other feeds
+            (
+                "https://www.bbc.co.uk/news/articles/c0abc?at_medium=RSS&at_campaign=rss",
+                "https://www.bbc.co.uk/news/articles/c0abc",
+            ),
+            (
+                "https://www.google.com/url?rct=j&url=https://www.bbc.co.uk/news/world-1&ct=ga",
+                "https://www.bbc.co.uk/news/world-1",
+            ),
+            (
+                "https://news.google.com/rss/articles/CBMiK2h0dHBz?oc=5",
+                "https://news.google.com/rss/articles/CBMiK2h0dHBz",
+            ),
+            // jokeapi: the joke id is in the query
+            (
+                "https://sv443.net/jokeapi/v2/joke/Any?idRange=42",
+                "https://sv443.net/jokeapi/v2/joke/Any?idRange=42",
+            ),
+            // jsonjoke: the fragment tells jokes from one endpoint apart
+            (
+                "https://official-joke-api.appspot.com/random_joke#3f2a9c1b0d4e",
+                "https://official-joke-api.appspot.com/random_joke#3f2a9c1b0d4e",
+            ),
+            // dadjoke, chucknorris, uselessfacts
+            ("https://icanhazdadjoke.com/j/R7UfaahVfFd", "https://icanhazdadjoke.com/j/R7UfaahVfFd"),
+            (
+                "https://api.chucknorris.io/jokes/abc123",
+                "https://api.chucknorris.io/jokes/abc123",
+            ),
+            (
+                "https://uselessfacts.jsph.pl/api/v2/facts/0f2c",
+                "https://uselessfacts.jsph.pl/api/v2/facts/0f2c",
+            ),
+            // not a URL at all
+            ("  not a url ", "not a url"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(canonicalize(input), expected, "canonicalize({:?})", input);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_is_idempotent() {
+        let url = "https://www.reddit.com/r/funny/comments/9x/y/?utm_source=share#c";
+        assert_eq!(canonicalize(&canonicalize(url)), canonicalize(url));
+    }
+}
@@ -1,28 +1,42 @@
-use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::stream::{self, StreamExt};
 use serde::de::DeserializeOwned;
 
+use super::error::FetchError;
+use super::provider::FetchedItem;
+use crate::images::{self, ImageData};
+use crate::net::HttpService;
+
 pub async fn fetch_json<T: DeserializeOwned>(
-    client: &reqwest::Client,
+    client: &HttpService,
     url: &str,
-) -> Result<T, ()> {
+) -> Result<T, FetchError> {
     let response = client
         .get(url)
-        .header("User-Agent", "cazzmachine/0.1.0")
         .send()
-        .await
-        .map_err(|_| ())?;
-    
+        .await?;
+
     if !response.status().is_success() {
-        return Err(());
+        return Err(FetchError::from_response(&response));
     }
-    
-    response.json::<T>().await.map_err(|_| ())
+
+    Ok(response.json::<T>().await?)
 }
 
-pub async fn download_image(client: &reqwest::Client, url: &str) -> Option<String> {
+/// Turns an empty result into `FetchError::Empty` so callers can tell
+/// "nothing there" apart from a successful fetch.
+pub(crate) fn non_empty(items: Vec<FetchedItem>) -> Result<Vec<FetchedItem>, FetchError> {
+    if items.is_empty() {
+        Err(FetchError::Empty)
+    } else {
+        Ok(items)
+    }
+}
+
+/// Downloads `url` as a thumbnail (see `images::thumbnail`). Responses that
+/// aren't images, or are larger than `images::MAX_DOWNLOAD_BYTES`, give `None`.
+pub async fn download_image(client: &HttpService, url: &str) -> Option<ImageData> {
     let response = client
         .get(url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
         .header("Referer", "https://www.reddit.com/")
         .send()
         .await
@@ -31,28 +45,49 @@ pub async fn download_image(client: &reqwest::Client, url: &str) -> Option<Strin
     if !response.status().is_success() {
         return None;
     }
+    if response.content_length().is_some_and(|len| len > images::MAX_DOWNLOAD_BYTES as u64) {
+        return None;
+    }
 
     let bytes = response.bytes().await.ok()?;
-    let mime = if url.contains(".png") {
-        "image/png"
-    } else if url.contains(".gif") {
-        "image/gif"
-    } else if url.contains(".webp") {
-        "image/webp"
-    } else {
-        "image/jpeg"
-    };
+    let max_px = images::thumbnail_max_px();
+    tokio::task::spawn_blocking(move || images::thumbnail(&bytes, max_px))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Downloads the thumbnails of `items` concurrently, bounded by the crawl
+/// concurrency limit.
+pub async fn download_thumbnails(client: &HttpService, items: &mut [FetchedItem]) {
+    let urls: Vec<Option<String>> = items.iter().map(|item| item.thumbnail_url.clone()).collect();
+    let downloads: Vec<Option<ImageData>> = stream::iter(urls)
+        .map(|url| async move {
+            match url {
+                Some(url) => download_image(client, &url).await,
+                None => None,
+            }
+        })
+        .buffered(super::concurrency_limit())
+        .collect()
+        .await;
+
+    for (item, data) in items.iter_mut().zip(downloads) {
+        item.thumbnail_data = data;
+    }
+}
 
-    let base64 = STANDARD.encode(&bytes);
-    Some(format!("data:{};base64,{}", mime, base64))
+/// Host of `url` without a leading `www.`, e.g. `github.com`.
+pub(crate) fn url_domain(url: &str) -> Option<String> {
+    let host = url.split("://").nth(1)?.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?.trim_start_matches("www.");
+    (!host.is_empty()).then(|| host.to_lowercase())
 }
 
-pub(crate) fn urlencoded(s: &str) -> String {
-    s.replace(':', "%3A")
-        .replace('/', "%2F")
-        .replace('?', "%3F")
-        .replace('=', "%3D")
-        .replace('&', "%26")
+/// Formats a Unix timestamp the way `fetched_at` is stored.
+pub(crate) fn local_timestamp(secs: i64) -> Option<String> {
+    let utc = chrono::DateTime::from_timestamp(secs, 0)?;
+    Some(utc.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
 }
 
 pub(crate) fn strip_html(s: &str) -> String {
@@ -1,4 +1,8 @@
+use super::error::FetchError;
 use super::provider::{ContentProvider, FetchedItem};
+use crate::net::{HostLimit, HttpService};
+use super::reddit::RedditPostMeta;
+use super::util::non_empty;
 use serde::Deserialize;
 
 pub struct RedditVideoProvider;
@@ -32,6 +36,8 @@ struct RedditPost {
     stickied: bool,
     #[serde(default)]
     is_video: bool,
+    #[serde(flatten)]
+    meta: RedditPostMeta,
 }
 
 #[async_trait::async_trait]
@@ -44,7 +50,11 @@ impl ContentProvider for RedditVideoProvider {
         "video"
     }
 
-    async fn fetch(&self, client: &reqwest::Client) -> Vec<FetchedItem> {
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        super::reddit::rate_limits()
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
         let subreddits = [
             "videos", "Unexpected", "WhatCouldGoWrong", "ContagiousLaughter",
             "WinStupidPrizes", "IdiotsInCars", "InstantKarma", "JusticeServed",
@@ -54,10 +64,7 @@ impl ContentProvider for RedditVideoProvider {
         let sub = subreddits[rand::random::<usize>() % subreddits.len()];
         let url = format!("https://www.reddit.com/r/{}/hot.json?limit=25", sub);
 
-        let listing: RedditListing = match super::util::fetch_json(client, &url).await {
-            Ok(l) => l,
-            Err(_) => return vec![],
-        };
+        let listing: RedditListing = super::util::fetch_json(client, &url).await?;
 
         let mut items = Vec::new();
         for c in listing.data.children.into_iter().take(5) {
@@ -71,23 +78,19 @@ impl ContentProvider for RedditVideoProvider {
                 None
             };
 
-            let thumbnail_data = if let Some(ref url) = thumb {
-                super::util::download_image(client, url).await
-            } else {
-                None
-            };
-
             items.push(FetchedItem {
                 source: format!("r/{}", sub),
                 category: "video".into(),
                 title: post.title,
                 url: format!("https://reddit.com{}", post.permalink),
                 thumbnail_url: thumb,
-                thumbnail_data,
+                thumbnail_data: None,
                 description: None,
+                metadata: post.meta.into_metadata(),
             });
         }
 
-        items
+        super::util::download_thumbnails(client, &mut items).await;
+        non_empty(items)
     }
 }
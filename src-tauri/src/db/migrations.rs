@@ -0,0 +1,421 @@
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+use std::path::{Path, PathBuf};
+
+/// One schema change. `version` is the `PRAGMA user_version` the database is
+/// at once `apply` has run; versions are consecutive, starting at 1.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub apply: fn(&Transaction) -> SqlResult<()>,
+}
+
+/// Every migration, oldest first. Append new steps here; never edit or
+/// reorder a step that has shipped.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        apply: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "provider_runs",
+        apply: provider_runs,
+    },
+    Migration {
+        version: 3,
+        description: "user-defined sources",
+        apply: sources,
+    },
+    Migration {
+        version: 4,
+        description: "mute list filter rules",
+        apply: filter_rules,
+    },
+    Migration {
+        version: 5,
+        description: "full-text search index",
+        apply: search_index,
+    },
+    Migration {
+        version: 6,
+        description: "saved item library",
+        apply: library,
+    },
+    Migration {
+        version: 7,
+        description: "daily stats rollup",
+        apply: stats_rollup,
+    },
+    Migration {
+        version: 8,
+        description: "item engagement metadata",
+        apply: item_metadata,
+    },
+    Migration {
+        version: 9,
+        description: "near-duplicate detection",
+        apply: dedupe,
+    },
+    Migration {
+        version: 10,
+        description: "content-addressed image store",
+        apply: image_store,
+    },
+];
+
+/// How many backups taken before migrating are kept around.
+const BACKUPS_KEPT: usize = 3;
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn current_version(conn: &Connection) -> SqlResult<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Brings the schema up to `latest_version()`, one transaction per step.
+///
+/// If the database already holds tables, a copy is written to `backup_dir`
+/// first. Returns the versions that were applied.
+pub fn run(conn: &mut Connection, backup_dir: &Path) -> SqlResult<Vec<i32>> {
+    let current = current_version(conn)?;
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if !is_empty_database(conn)? {
+        backup(conn, backup_dir, current)?;
+    }
+
+    let mut applied = Vec::new();
+    for migration in pending {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+fn is_empty_database(conn: &Connection) -> SqlResult<bool> {
+    let tables: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(tables == 0)
+}
+
+/// Writes a consistent copy of the database with `VACUUM INTO` and prunes
+/// all but the newest `BACKUPS_KEPT` backups.
+pub fn backup(conn: &Connection, backup_dir: &Path, version: i32) -> SqlResult<PathBuf> {
+    std::fs::create_dir_all(backup_dir).ok();
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f");
+    // Timestamp first so file names sort chronologically
+    let path = backup_dir.join(format!("cazzmachine-{}-v{}.db", stamp, version));
+    conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
+
+    if let Ok(entries) = std::fs::read_dir(backup_dir) {
+        let mut backups: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "db"))
+            .collect();
+        backups.sort();
+        let excess = backups.len().saturating_sub(BACKUPS_KEPT);
+        for old in backups.into_iter().take(excess) {
+            std::fs::remove_file(old).ok();
+        }
+    }
+
+    Ok(path)
+}
+
+fn has_column(tx: &Transaction, table: &str, column: &str) -> SqlResult<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqlResult<Vec<_>>>()?;
+    Ok(names.iter().any(|n| n == column))
+}
+
+/// The schema as it stood before versioning. Databases created by older
+/// builds already have these tables (user_version 0), so everything is
+/// `IF NOT EXISTS`, and columns that were added to `crawl_items` after its
+/// first release are backfilled.
+fn baseline(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS crawl_items (
+            id TEXT PRIMARY KEY,
+            source TEXT NOT NULL,
+            category TEXT NOT NULL,
+            title TEXT NOT NULL,
+            url TEXT NOT NULL UNIQUE,
+            thumbnail_url TEXT,
+            thumbnail_data TEXT,
+            description TEXT,
+            fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
+            is_seen INTEGER NOT NULL DEFAULT 0,
+            is_saved INTEGER NOT NULL DEFAULT 0,
+            is_consumed INTEGER NOT NULL DEFAULT 0,
+            session_date TEXT NOT NULL DEFAULT (date('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS session_stats (
+            id TEXT PRIMARY KEY,
+            date TEXT NOT NULL UNIQUE,
+            started_at TEXT NOT NULL,
+            memes_found INTEGER NOT NULL DEFAULT 0,
+            jokes_found INTEGER NOT NULL DEFAULT 0,
+            news_checked INTEGER NOT NULL DEFAULT 0,
+            videos_found INTEGER NOT NULL DEFAULT 0,
+            gossip_found INTEGER NOT NULL DEFAULT 0,
+            total_items INTEGER NOT NULL DEFAULT 0,
+            estimated_time_saved_minutes REAL NOT NULL DEFAULT 0.0
+        );
+
+        CREATE TABLE IF NOT EXISTS notification_log (
+            id TEXT PRIMARY KEY,
+            message TEXT NOT NULL,
+            sent_at TEXT NOT NULL DEFAULT (datetime('now')),
+            related_item_id TEXT,
+            FOREIGN KEY (related_item_id) REFERENCES crawl_items(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS app_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT OR IGNORE INTO app_state (key, value) VALUES ('last_active_timestamp', strftime('%s', 'now') * 1000);
+
+        CREATE TABLE IF NOT EXISTS diagnostic_logs (
+            id TEXT PRIMARY KEY,
+            timestamp TEXT NOT NULL,
+            event_type TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            message TEXT NOT NULL,
+            related_item_id TEXT,
+            metadata TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_diagnostic_logs_severity ON diagnostic_logs(severity);
+        CREATE INDEX IF NOT EXISTS idx_diagnostic_logs_event ON diagnostic_logs(event_type);
+    "#)?;
+
+    for (column, definition) in [
+        ("thumbnail_data", "TEXT"),
+        ("description", "TEXT"),
+        ("is_consumed", "INTEGER NOT NULL DEFAULT 0"),
+    ] {
+        if !has_column(tx, "crawl_items", column)? {
+            tx.execute_batch(&format!("ALTER TABLE crawl_items ADD COLUMN {} {};", column, definition))?;
+        }
+    }
+
+    tx.execute_batch(r#"
+        CREATE INDEX IF NOT EXISTS idx_crawl_items_session ON crawl_items(session_date);
+        CREATE INDEX IF NOT EXISTS idx_crawl_items_category ON crawl_items(category);
+        CREATE INDEX IF NOT EXISTS idx_crawl_items_consumed ON crawl_items(is_consumed);
+    "#)
+}
+
+// Steps 2-4 predate the migration runner, so unversioned databases may
+// already contain their tables.
+
+fn provider_runs(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS provider_runs (
+            id TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            outcome TEXT NOT NULL,
+            items_fetched INTEGER NOT NULL DEFAULT 0,
+            items_new INTEGER NOT NULL DEFAULT 0,
+            error TEXT,
+            status_code INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_provider_runs_provider ON provider_runs(provider, started_at);
+    "#)
+}
+
+fn sources(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS sources (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            category TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            UNIQUE(kind, target)
+        );
+    "#)
+}
+
+fn filter_rules(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE IF NOT EXISTS filter_rules (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            hit_count INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            UNIQUE(kind, pattern)
+        );
+    "#)
+}
+
+/// External-content FTS5 index over `crawl_items`, keyed by its implicit
+/// rowid and kept in sync by triggers, so inserts, prune updates and deletes
+/// need no extra bookkeeping. `crawl_items` has no INTEGER PRIMARY KEY, so a
+/// plain `VACUUM` may renumber rowids: follow one with a `'rebuild'`.
+fn search_index(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE VIRTUAL TABLE crawl_items_fts USING fts5(
+            title,
+            description,
+            source,
+            content = 'crawl_items',
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER crawl_items_fts_insert AFTER INSERT ON crawl_items BEGIN
+            INSERT INTO crawl_items_fts (rowid, title, description, source)
+            VALUES (new.rowid, new.title, new.description, new.source);
+        END;
+
+        CREATE TRIGGER crawl_items_fts_delete AFTER DELETE ON crawl_items BEGIN
+            INSERT INTO crawl_items_fts (crawl_items_fts, rowid, title, description, source)
+            VALUES ('delete', old.rowid, old.title, old.description, old.source);
+        END;
+
+        CREATE TRIGGER crawl_items_fts_update AFTER UPDATE OF title, description, source ON crawl_items BEGIN
+            INSERT INTO crawl_items_fts (crawl_items_fts, rowid, title, description, source)
+            VALUES ('delete', old.rowid, old.title, old.description, old.source);
+            INSERT INTO crawl_items_fts (rowid, title, description, source)
+            VALUES (new.rowid, new.title, new.description, new.source);
+        END;
+
+        INSERT INTO crawl_items_fts (crawl_items_fts) VALUES ('rebuild');
+    "#)
+}
+
+/// Saved items get a library entry; items saved before the library existed
+/// are carried over with their fetch time as `saved_at`.
+fn library(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE library (
+            item_id TEXT PRIMARY KEY REFERENCES crawl_items(id) ON DELETE CASCADE,
+            saved_at TEXT NOT NULL,
+            note TEXT
+        );
+        CREATE INDEX idx_library_saved_at ON library(saved_at);
+
+        CREATE TABLE library_tags (
+            item_id TEXT NOT NULL REFERENCES library(item_id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (item_id, tag)
+        );
+        CREATE INDEX idx_library_tags_tag ON library_tags(tag);
+
+        INSERT INTO library (item_id, saved_at)
+        SELECT id, fetched_at FROM crawl_items WHERE is_saved = 1;
+    "#)
+}
+
+/// `session_stats` becomes the per-day rollup: today's row only counts
+/// consume calls, and `rolled_up_at` is set once a finished day is written.
+fn stats_rollup(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        ALTER TABLE session_stats ADD COLUMN providers_used INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE session_stats ADD COLUMN consume_calls INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE session_stats ADD COLUMN rolled_up_at TEXT;
+    "#)
+}
+
+/// Typed provider metadata on `crawl_items`, NULL where unknown.
+fn item_metadata(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        ALTER TABLE crawl_items ADD COLUMN score INTEGER;
+        ALTER TABLE crawl_items ADD COLUMN comments INTEGER;
+        ALTER TABLE crawl_items ADD COLUMN author TEXT;
+        ALTER TABLE crawl_items ADD COLUMN published_at TEXT;
+        ALTER TABLE crawl_items ADD COLUMN media_duration_secs REAL;
+        ALTER TABLE crawl_items ADD COLUMN domain TEXT;
+    "#)
+}
+
+/// Fingerprints of stored items, and the copies that were not stored because
+/// they duplicate one. Items from before this step have no fingerprint and
+/// are never matched.
+fn dedupe(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE item_fingerprints (
+            item_id TEXT PRIMARY KEY REFERENCES crawl_items(id) ON DELETE CASCADE,
+            normalized_url TEXT NOT NULL,
+            title_minhash BLOB,
+            image_dhash INTEGER
+        );
+        CREATE INDEX idx_item_fingerprints_url ON item_fingerprints(normalized_url);
+
+        CREATE TABLE item_duplicates (
+            id TEXT PRIMARY KEY,
+            canonical_id TEXT NOT NULL REFERENCES crawl_items(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            title TEXT NOT NULL,
+            url TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            similarity REAL NOT NULL,
+            detected_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_item_duplicates_canonical ON item_duplicates(canonical_id);
+    "#)
+}
+
+/// Thumbnails move from base64 data URLs in `crawl_items.thumbnail_data`
+/// into `images`, keyed by the SHA-256 of their bytes; items keep the hash.
+/// Values that don't decode are dropped, leaving the item to fall back to
+/// its `thumbnail_url`.
+fn image_store(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(r#"
+        CREATE TABLE images (
+            hash TEXT PRIMARY KEY,
+            mime TEXT NOT NULL,
+            data BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            last_used_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_images_last_used ON images(last_used_at);
+
+        ALTER TABLE crawl_items ADD COLUMN thumbnail_hash TEXT;
+        CREATE INDEX idx_crawl_items_thumbnail ON crawl_items(thumbnail_hash);
+    "#)?;
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt = tx.prepare("SELECT id, thumbnail_data FROM crawl_items WHERE thumbnail_data IS NOT NULL")?;
+    let thumbnails = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<SqlResult<Vec<_>>>()?;
+    drop(stmt);
+
+    for (id, data_url) in thumbnails {
+        let Some(image) = crate::images::ImageData::from_data_url(&data_url) else {
+            continue;
+        };
+        let hash = image.hash();
+        tx.execute(
+            "INSERT OR IGNORE INTO images (hash, mime, data, size, created_at, last_used_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            rusqlite::params![hash, image.mime, image.bytes, image.bytes.len() as i64, now],
+        )?;
+        tx.execute("UPDATE crawl_items SET thumbnail_hash = ?1 WHERE id = ?2", rusqlite::params![hash, id])?;
+    }
+
+    tx.execute_batch("ALTER TABLE crawl_items DROP COLUMN thumbnail_data;")
+}
@@ -1,9 +1,14 @@
+pub mod migrations;
 pub mod models;
 
-use rusqlite::{Connection, Result as SqlResult};
+use rusqlite::{Connection, OptionalExtension, Result as SqlResult};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+use crate::consumption;
+use crate::crawler::dedupe::{self, Fingerprint};
+use crate::images::{self, ImageData};
+
 pub struct Database {
     pub conn: Mutex<Connection>,
 }
@@ -12,88 +17,41 @@ impl Database {
     pub fn new(app_dir: PathBuf) -> SqlResult<Self> {
         std::fs::create_dir_all(&app_dir).ok();
         let db_path = app_dir.join("cazzmachine.db");
-        let conn = Connection::open(db_path)?;
+        let mut conn = Connection::open(db_path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
+        migrations::run(&mut conn, &app_dir.join("backups"))?;
 
-        let db = Database {
+        Ok(Database {
             conn: Mutex::new(conn),
-        };
-        db.run_migrations()?;
-        Ok(db)
-    }
-
-    fn run_migrations(&self) -> SqlResult<()> {
-        let conn = self.conn.lock().unwrap();
-
-        conn.execute_batch(r#"
-            CREATE TABLE IF NOT EXISTS crawl_items (
-                id TEXT PRIMARY KEY,
-                source TEXT NOT NULL,
-                category TEXT NOT NULL,
-                title TEXT NOT NULL,
-                url TEXT NOT NULL UNIQUE,
-                thumbnail_url TEXT,
-                thumbnail_data TEXT,
-                description TEXT,
-                fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
-                is_seen INTEGER NOT NULL DEFAULT 0,
-                is_saved INTEGER NOT NULL DEFAULT 0,
-                is_consumed INTEGER NOT NULL DEFAULT 0,
-                session_date TEXT NOT NULL DEFAULT (date('now'))
-            );
-            CREATE INDEX IF NOT EXISTS idx_crawl_items_session ON crawl_items(session_date);
-            CREATE INDEX IF NOT EXISTS idx_crawl_items_category ON crawl_items(category);
-            CREATE INDEX IF NOT EXISTS idx_crawl_items_consumed ON crawl_items(is_consumed);
-            
-            CREATE TABLE IF NOT EXISTS session_stats (
-                id TEXT PRIMARY KEY,
-                date TEXT NOT NULL UNIQUE,
-                started_at TEXT NOT NULL,
-                memes_found INTEGER NOT NULL DEFAULT 0,
-                jokes_found INTEGER NOT NULL DEFAULT 0,
-                news_checked INTEGER NOT NULL DEFAULT 0,
-                videos_found INTEGER NOT NULL DEFAULT 0,
-                gossip_found INTEGER NOT NULL DEFAULT 0,
-                total_items INTEGER NOT NULL DEFAULT 0,
-                estimated_time_saved_minutes REAL NOT NULL DEFAULT 0.0
-            );
-            
-            CREATE TABLE IF NOT EXISTS notification_log (
-                id TEXT PRIMARY KEY,
-                message TEXT NOT NULL,
-                sent_at TEXT NOT NULL DEFAULT (datetime('now')),
-                related_item_id TEXT,
-                FOREIGN KEY (related_item_id) REFERENCES crawl_items(id)
-            );
-            
-            CREATE TABLE IF NOT EXISTS app_state (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            );
-            INSERT OR IGNORE INTO app_state (key, value) VALUES ('last_active_timestamp', strftime('%s', 'now') * 1000);
-            
-            CREATE TABLE IF NOT EXISTS diagnostic_logs (
-                id TEXT PRIMARY KEY,
-                timestamp TEXT NOT NULL,
-                event_type TEXT NOT NULL,
-                severity TEXT NOT NULL,
-                message TEXT NOT NULL,
-                related_item_id TEXT,
-                metadata TEXT
-            );
-            CREATE INDEX IF NOT EXISTS idx_diagnostic_logs_severity ON diagnostic_logs(severity);
-            CREATE INDEX IF NOT EXISTS idx_diagnostic_logs_event ON diagnostic_logs(event_type);
-        "#)?;
+        })
+    }
 
-        Ok(())
+    /// Runs `f` in a single transaction. It is committed only when `commit`
+    /// is true, which lets bulk operations be previewed by rolling back.
+    pub fn with_transaction<T>(
+        &self,
+        commit: bool,
+        f: impl FnOnce(&rusqlite::Transaction) -> SqlResult<T>,
+    ) -> SqlResult<T> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let result = f(&tx)?;
+        if commit {
+            tx.commit()?;
+        }
+        Ok(result)
     }
 
     pub fn insert_item(&self, item: &models::CrawlItem) -> SqlResult<bool> {
         let conn = self.conn.lock().unwrap();
-        let result = conn.execute(
-            "INSERT OR IGNORE INTO crawl_items (id, source, category, title, url, thumbnail_url, thumbnail_data, description, fetched_at, session_date)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        Self::insert_item_with_conn(&conn, item)
+    }
+
+    fn insert_item_with_conn(conn: &Connection, item: &models::CrawlItem) -> SqlResult<bool> {
+        let rows = conn.execute(
+            "INSERT OR IGNORE INTO crawl_items (id, source, category, title, url, thumbnail_url, thumbnail_hash, description, fetched_at, session_date,
+                                                score, comments, author, published_at, media_duration_secs, domain)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
             rusqlite::params![
                 item.id,
                 item.source,
@@ -101,16 +59,142 @@ impl Database {
                 item.title,
                 item.url,
                 item.thumbnail_url,
-                item.thumbnail_data,
+                item.thumbnail_hash,
                 item.description,
                 item.fetched_at,
                 item.session_date,
+                item.metadata.score,
+                item.metadata.comments,
+                item.metadata.author,
+                item.metadata.published_at,
+                item.metadata.media_duration_secs,
+                item.metadata.domain,
             ],
-        );
-        match result {
-            Ok(rows) => Ok(rows > 0),
-            Err(e) => Err(e),
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Stores `item` unless it copies a recent item of the same category, in
+    /// which case the copy is linked to that item instead. The check and the
+    /// insert happen under one lock, so concurrent providers can't both store
+    /// the same story. `thumbnail`, whose hash `item.thumbnail_hash` should
+    /// be, is only stored along with a stored item.
+    pub fn insert_item_deduped(
+        &self,
+        item: &models::CrawlItem,
+        fingerprint: &Fingerprint,
+        thumbnail: Option<&ImageData>,
+    ) -> SqlResult<models::InsertOutcome> {
+        let conn = self.conn.lock().unwrap();
+        let known: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM crawl_items WHERE id = ?1 OR url = ?2)
+                 OR EXISTS(SELECT 1 FROM item_duplicates WHERE id = ?1)",
+            rusqlite::params![item.id, item.url],
+            |row| row.get(0),
+        )?;
+        if known {
+            return Ok(models::InsertOutcome::AlreadyStored);
         }
+
+        let since = (chrono::Local::now() - chrono::Duration::days(dedupe::WINDOW_DAYS))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        let mut stmt = conn.prepare(
+            "SELECT f.item_id, f.normalized_url, f.title_minhash, f.image_dhash
+             FROM item_fingerprints f JOIN crawl_items c ON c.id = f.item_id
+             WHERE (c.category = ?1 AND c.fetched_at >= ?2) OR f.normalized_url = ?3
+             ORDER BY c.fetched_at, c.rowid",
+        )?;
+        let candidates = stmt
+            .query_map(
+                rusqlite::params![item.category, since, fingerprint.normalized_url],
+                |row| {
+                    let minhash: Option<Vec<u8>> = row.get(2)?;
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        Fingerprint {
+                            normalized_url: row.get(1)?,
+                            title_minhash: minhash.map(|bytes| {
+                                bytes
+                                    .chunks_exact(4)
+                                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                                    .collect()
+                            }),
+                            image_dhash: row.get::<_, Option<i64>>(3)?.map(|h| h as u64),
+                        },
+                    ))
+                },
+            )?
+            .collect::<SqlResult<Vec<_>>>()?;
+
+        if let Some((canonical_id, reason, similarity)) = dedupe::find_canonical(fingerprint, &candidates) {
+            conn.execute(
+                "INSERT OR IGNORE INTO item_duplicates (id, canonical_id, source, title, url, reason, similarity, detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    item.id,
+                    canonical_id,
+                    item.source,
+                    item.title,
+                    item.url,
+                    reason.as_str(),
+                    similarity,
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                ],
+            )?;
+            return Ok(models::InsertOutcome::Duplicate {
+                canonical_id: canonical_id.to_string(),
+                reason: reason.as_str().to_string(),
+            });
+        }
+        drop(stmt);
+
+        if !Self::insert_item_with_conn(&conn, item)? {
+            return Ok(models::InsertOutcome::AlreadyStored);
+        }
+        let minhash: Option<Vec<u8>> = fingerprint
+            .title_minhash
+            .as_ref()
+            .map(|signature| signature.iter().flat_map(|h| h.to_le_bytes()).collect());
+        conn.execute(
+            "INSERT OR REPLACE INTO item_fingerprints (item_id, normalized_url, title_minhash, image_dhash)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                item.id,
+                fingerprint.normalized_url,
+                minhash,
+                fingerprint.image_dhash.map(|h| h as i64),
+            ],
+        )?;
+        if let Some(thumbnail) = thumbnail {
+            Self::store_image_with_conn(&conn, thumbnail)?;
+        }
+        Ok(models::InsertOutcome::Inserted)
+    }
+
+    /// Copies of `item_id` that were linked to it instead of being stored.
+    pub fn get_item_duplicates(&self, item_id: &str) -> SqlResult<Vec<models::ItemDuplicate>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, canonical_id, source, title, url, reason, similarity, detected_at
+             FROM item_duplicates WHERE canonical_id = ?1
+             ORDER BY detected_at, rowid",
+        )?;
+        let duplicates = stmt
+            .query_map([item_id], |row| {
+                Ok(models::ItemDuplicate {
+                    id: row.get(0)?,
+                    canonical_id: row.get(1)?,
+                    source: row.get(2)?,
+                    title: row.get(3)?,
+                    url: row.get(4)?,
+                    reason: row.get(5)?,
+                    similarity: row.get(6)?,
+                    detected_at: row.get(7)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(duplicates)
     }
 
     fn row_to_item(row: &rusqlite::Row) -> rusqlite::Result<models::CrawlItem> {
@@ -121,18 +205,31 @@ impl Database {
             title: row.get(3)?,
             url: row.get(4)?,
             thumbnail_url: row.get(5)?,
-            thumbnail_data: row.get(6)?,
+            thumbnail_hash: row.get(6)?,
             description: row.get(7)?,
             fetched_at: row.get(8)?,
             is_seen: row.get(9)?,
             is_saved: row.get(10)?,
             is_consumed: row.get(11)?,
             session_date: row.get(12)?,
+            metadata: models::ItemMetadata {
+                score: row.get(13)?,
+                comments: row.get(14)?,
+                author: row.get(15)?,
+                published_at: row.get(16)?,
+                media_duration_secs: row.get(17)?,
+                domain: row.get(18)?,
+            },
         })
     }
 
     const ITEM_COLUMNS: &'static str =
-        "id, source, category, title, url, thumbnail_url, thumbnail_data, description, fetched_at, is_seen, is_saved, is_consumed, session_date";
+        "id, source, category, title, url, thumbnail_url, thumbnail_hash, description, fetched_at, is_seen, is_saved, is_consumed, session_date, \
+         score, comments, author, published_at, media_duration_secs, domain";
+
+    /// Number of `ITEM_COLUMNS`; queries that select more columns after them
+    /// read those from this index on.
+    const ITEM_COLUMN_COUNT: usize = 19;
 
     pub fn get_items_for_today(&self) -> SqlResult<Vec<models::CrawlItem>> {
         let conn = self.conn.lock().unwrap();
@@ -162,14 +259,126 @@ impl Database {
         Ok(items)
     }
 
+    /// Largest page `search_items` returns.
+    const MAX_SEARCH_PAGE: i64 = 100;
+
+    /// What `highlight()` and `snippet()` put around matched terms: control
+    /// characters, so they can't be confused with the crawled text.
+    const MATCH_START: char = '\u{2}';
+    const MATCH_END: char = '\u{3}';
+
+    /// HTML-escapes crawled text and turns the match delimiters into
+    /// `<mark>` tags.
+    fn mark_matches(text: &str) -> String {
+        let mut html = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                Self::MATCH_START => html.push_str("<mark>"),
+                Self::MATCH_END => html.push_str("</mark>"),
+                '&' => html.push_str("&amp;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                '"' => html.push_str("&quot;"),
+                '\'' => html.push_str("&#39;"),
+                c => html.push(c),
+            }
+        }
+        html
+    }
+
+    /// Turns free text into an FTS5 query: every word becomes a quoted
+    /// prefix term, so operators and punctuation in the input are inert.
+    fn fts_query(query: &str) -> Option<String> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|word| word.replace('"', ""))
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+
+    /// Ranked full-text search over consumed items of every day, matching
+    /// title (weighted highest), description and source.
+    pub fn search_items(
+        &self,
+        query: &str,
+        filters: &models::SearchFilters,
+        paging: &models::Paging,
+    ) -> SqlResult<models::SearchResults> {
+        let Some(fts_query) = Self::fts_query(query) else {
+            return Ok(models::SearchResults { hits: Vec::new(), total: 0 });
+        };
+        let conn = self.conn.lock().unwrap();
+
+        let conditions = "crawl_items_fts MATCH ?1
+               AND c.is_consumed = 1
+               AND (?2 IS NULL OR c.category = ?2)
+               AND (?3 IS NULL OR c.source = ?3)
+               AND (?4 = 0 OR c.is_saved = 1)
+               AND (?5 IS NULL OR c.session_date >= ?5)
+               AND (?6 IS NULL OR c.session_date <= ?6)";
+        let params = rusqlite::params![
+            fts_query,
+            filters.category,
+            filters.source,
+            filters.saved_only,
+            filters.from_date,
+            filters.to_date,
+        ];
+
+        let total: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM crawl_items_fts JOIN crawl_items c ON c.rowid = crawl_items_fts.rowid WHERE {}",
+                conditions
+            ),
+            params,
+            |row| row.get(0),
+        )?;
+
+        let columns: Vec<String> = Self::ITEM_COLUMNS.split(", ").map(|c| format!("c.{}", c)).collect();
+        let sql = format!(
+            "SELECT {},
+                    highlight(crawl_items_fts, 0, char(2), char(3)),
+                    snippet(crawl_items_fts, -1, char(2), char(3), '…', 16),
+                    bm25(crawl_items_fts, 10.0, 3.0, 1.0) AS rank
+             FROM crawl_items_fts JOIN crawl_items c ON c.rowid = crawl_items_fts.rowid
+             WHERE {}
+             ORDER BY rank
+             LIMIT {} OFFSET {}",
+            columns.join(", "),
+            conditions,
+            paging.limit.clamp(1, Self::MAX_SEARCH_PAGE),
+            paging.offset.max(0)
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let hits = stmt
+            .query_map(params, |row| {
+                Ok(models::SearchHit {
+                    item: Self::row_to_item(row)?,
+                    title_highlighted: Self::mark_matches(&row.get::<_, String>(Self::ITEM_COLUMN_COUNT)?),
+                    snippet: Self::mark_matches(&row.get::<_, String>(Self::ITEM_COLUMN_COUNT + 1)?),
+                    rank: row.get(Self::ITEM_COLUMN_COUNT + 2)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+
+        Ok(models::SearchResults { hits, total })
+    }
+
     pub fn get_today_stats(&self) -> SqlResult<models::DayStats> {
         let conn = self.conn.lock().unwrap();
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        Ok(Self::day_stats_with_conn(&conn, &today)?.stats)
+    }
+
+    /// Stats for one session date, computed from its consumed items.
+    fn day_stats_with_conn(conn: &Connection, date: &str) -> SqlResult<models::DailyStats> {
         let mut stmt = conn.prepare(
             "SELECT category, COUNT(*) FROM crawl_items WHERE session_date = ?1 AND is_consumed = 1 GROUP BY category",
         )?;
         let mut stats = models::DayStats::default();
-        let rows = stmt.query_map(rusqlite::params![today], |row| {
+        let rows = stmt.query_map(rusqlite::params![date], |row| {
             let category: String = row.get(0)?;
             let count: i64 = row.get(1)?;
             Ok((category, count))
@@ -186,20 +395,285 @@ impl Database {
             }
             stats.total_items += count;
         }
-        stats.estimated_time_saved_minutes = Self::estimate_time_saved(&stats);
-        Ok(stats)
+        stats.estimated_time_saved_minutes = Self::item_costs_with_conn(conn, date, true, &consumption::CostModel::current())?
+            .iter()
+            .map(|(_, cost)| cost)
+            .sum();
+
+        let providers_used: i64 = conn.query_row(
+            "SELECT COUNT(DISTINCT source) FROM crawl_items WHERE session_date = ?1 AND is_consumed = 1",
+            rusqlite::params![date],
+            |row| row.get(0),
+        )?;
+        let consume_calls: i64 = conn
+            .query_row(
+                "SELECT consume_calls FROM session_stats WHERE date = ?1",
+                rusqlite::params![date],
+                |row| row.get(0),
+            )
+            .unwrap_or(0);
+
+        Ok(models::DailyStats {
+            date: date.to_string(),
+            stats,
+            providers_used,
+            consume_calls,
+        })
     }
 
+    /// Writes the rollup row for every finished day that doesn't have one yet
+    /// and returns how many days were rolled up. Idempotent, so it is safe to
+    /// call on every scheduler tick.
+    pub fn roll_up_stats(&self) -> SqlResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        Self::roll_up_stats_with_conn(&conn)
+    }
+
+    pub(crate) fn roll_up_stats_with_conn(conn: &Connection) -> SqlResult<usize> {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let mut stmt = conn.prepare(
+            "SELECT session_date FROM crawl_items WHERE session_date < ?1 AND is_consumed = 1
+             UNION
+             SELECT date FROM session_stats WHERE date < ?1
+             EXCEPT
+             SELECT date FROM session_stats WHERE rolled_up_at IS NOT NULL",
+        )?;
+        let dates = stmt
+            .query_map(rusqlite::params![today], |row| row.get::<_, String>(0))?
+            .collect::<SqlResult<Vec<_>>>()?;
+
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        for date in &dates {
+            let day = Self::day_stats_with_conn(conn, date)?;
+            let started_at: String = conn.query_row(
+                "SELECT COALESCE(MIN(fetched_at), ?1) FROM crawl_items WHERE session_date = ?1",
+                rusqlite::params![date],
+                |row| row.get(0),
+            )?;
+            conn.execute(
+                "INSERT INTO session_stats (id, date, started_at, memes_found, jokes_found, news_checked, videos_found, gossip_found, total_items, estimated_time_saved_minutes, providers_used, rolled_up_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(date) DO UPDATE SET
+                    memes_found = excluded.memes_found,
+                    jokes_found = excluded.jokes_found,
+                    news_checked = excluded.news_checked,
+                    videos_found = excluded.videos_found,
+                    gossip_found = excluded.gossip_found,
+                    total_items = excluded.total_items,
+                    estimated_time_saved_minutes = excluded.estimated_time_saved_minutes,
+                    providers_used = excluded.providers_used,
+                    rolled_up_at = excluded.rolled_up_at",
+                rusqlite::params![
+                    uuid::Uuid::new_v4().to_string(),
+                    date,
+                    started_at,
+                    day.stats.memes_found,
+                    day.stats.jokes_found,
+                    day.stats.news_checked,
+                    day.stats.videos_found,
+                    day.stats.gossip_found,
+                    day.stats.total_items,
+                    day.stats.estimated_time_saved_minutes,
+                    day.providers_used,
+                    now
+                ],
+            )?;
+        }
+        Ok(dates.len())
+    }
+
+    /// First day of `period`, ending today.
+    fn period_start(
+        conn: &Connection,
+        period: models::StatsPeriod,
+        today: chrono::NaiveDate,
+    ) -> SqlResult<chrono::NaiveDate> {
+        let start = match period {
+            models::StatsPeriod::Week => today - chrono::Duration::days(6),
+            models::StatsPeriod::Month => today - chrono::Duration::days(29),
+            models::StatsPeriod::All => {
+                let first: Option<String> = conn.query_row(
+                    "SELECT MIN(date) FROM session_stats",
+                    [],
+                    |row| row.get(0),
+                )?;
+                first
+                    .and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+                    .map_or(today, |d| d.min(today))
+            }
+        };
+        Ok(start)
+    }
+
+    /// One entry per day of `period`, oldest first; days without activity
+    /// are zero-filled so the series can be charted directly.
+    pub fn get_stats_series(&self, period: models::StatsPeriod) -> SqlResult<Vec<models::DailyStats>> {
+        let conn = self.conn.lock().unwrap();
+        Self::roll_up_stats_with_conn(&conn)?;
+        let today = chrono::Local::now().date_naive();
+        let start = Self::period_start(&conn, period, today)?;
+        Self::stats_between_with_conn(&conn, start, today)
+    }
+
+    /// Like `get_stats_series`, for an explicit inclusive date range. Either
+    /// end may be omitted; days after today are left out.
+    pub fn get_stats_between(
+        &self,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> SqlResult<Vec<models::DailyStats>> {
+        let conn = self.conn.lock().unwrap();
+        Self::roll_up_stats_with_conn(&conn)?;
+        let today = chrono::Local::now().date_naive();
+        let from = match from {
+            Some(from) => from,
+            None => Self::period_start(&conn, models::StatsPeriod::All, today)?,
+        };
+        Self::stats_between_with_conn(&conn, from, to.map_or(today, |to| to.min(today)))
+    }
+
+    fn stats_between_with_conn(
+        conn: &Connection,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> SqlResult<Vec<models::DailyStats>> {
+        let today = chrono::Local::now().date_naive();
+        let mut stmt = conn.prepare(
+            "SELECT date, memes_found, jokes_found, news_checked, videos_found, gossip_found, total_items, estimated_time_saved_minutes, providers_used, consume_calls
+             FROM session_stats WHERE date >= ?1 AND date <= ?2 AND rolled_up_at IS NOT NULL",
+        )?;
+        let mut rolled: std::collections::HashMap<String, models::DailyStats> = stmt
+            .query_map(
+                rusqlite::params![from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string()],
+                |row| {
+                    Ok(models::DailyStats {
+                        date: row.get(0)?,
+                        stats: models::DayStats {
+                            memes_found: row.get(1)?,
+                            jokes_found: row.get(2)?,
+                            news_checked: row.get(3)?,
+                            videos_found: row.get(4)?,
+                            gossip_found: row.get(5)?,
+                            total_items: row.get(6)?,
+                            estimated_time_saved_minutes: row.get(7)?,
+                        },
+                        providers_used: row.get(8)?,
+                        consume_calls: row.get(9)?,
+                    })
+                },
+            )?
+            .map(|day| day.map(|d| (d.date.clone(), d)))
+            .collect::<SqlResult<_>>()?;
+
+        let mut series = Vec::new();
+        for date in from.iter_days().take_while(|d| *d <= to && *d <= today) {
+            let date = date.format("%Y-%m-%d").to_string();
+            if date == today.format("%Y-%m-%d").to_string() {
+                // Today is still in progress, so it is computed live
+                series.push(Self::day_stats_with_conn(conn, &date)?);
+            } else {
+                series.push(rolled.remove(&date).unwrap_or(models::DailyStats {
+                    date,
+                    ..Default::default()
+                }));
+            }
+        }
+        Ok(series)
+    }
+
+    /// Consumed items with a session date in the inclusive range, oldest first.
+    pub fn get_consumed_items(
+        &self,
+        from_date: Option<&str>,
+        to_date: Option<&str>,
+    ) -> SqlResult<Vec<models::CrawlItem>> {
+        let conn = self.conn.lock().unwrap();
+        let sql = format!(
+            "SELECT {} FROM crawl_items
+             WHERE is_consumed = 1 AND (?1 IS NULL OR session_date >= ?1) AND (?2 IS NULL OR session_date <= ?2)
+             ORDER BY session_date, fetched_at, id",
+            Self::ITEM_COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let items = stmt
+            .query_map(rusqlite::params![from_date, to_date], Self::row_to_item)?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(items)
+    }
+
+    pub fn get_stats_summary(&self, period: models::StatsPeriod) -> SqlResult<models::StatsSummary> {
+        let series = self.get_stats_series(period)?;
+
+        let mut totals = models::DayStats::default();
+        let mut consume_calls = 0;
+        let mut providers = 0;
+        let mut days_active = 0;
+        for day in &series {
+            totals.memes_found += day.stats.memes_found;
+            totals.jokes_found += day.stats.jokes_found;
+            totals.news_checked += day.stats.news_checked;
+            totals.videos_found += day.stats.videos_found;
+            totals.gossip_found += day.stats.gossip_found;
+            totals.total_items += day.stats.total_items;
+            totals.estimated_time_saved_minutes += day.stats.estimated_time_saved_minutes;
+            consume_calls += day.consume_calls;
+            providers += day.providers_used;
+            if day.stats.total_items > 0 {
+                days_active += 1;
+            }
+        }
+
+        let days = series.len().max(1) as f64;
+        Ok(models::StatsSummary {
+            period,
+            from_date: series.first().map(|d| d.date.clone()).unwrap_or_default(),
+            to_date: series.last().map(|d| d.date.clone()).unwrap_or_default(),
+            days_active,
+            average_items_per_day: totals.total_items as f64 / days,
+            average_providers_per_day: providers as f64 / days,
+            totals,
+            consume_calls,
+        })
+    }
+
+    /// Consumes pending items with the strategy and cost model from settings.
     pub fn consume_pending_items(&self, budget_minutes: f64) -> SqlResult<models::ConsumeResult> {
+        let strategy = consumption::StrategyKind::current().strategy();
+        self.consume_pending_items_with(budget_minutes, strategy.as_ref(), &consumption::CostModel::current())
+    }
+
+    pub fn consume_pending_items_with(
+        &self,
+        budget_minutes: f64,
+        strategy: &dyn consumption::ConsumptionStrategy,
+        costs: &consumption::CostModel,
+    ) -> SqlResult<models::ConsumeResult> {
         let conn = self.conn.lock().unwrap();
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
 
+        // Engagement: how many items the user has saved from the same source
         let mut stmt = conn.prepare(
-            "SELECT id, category FROM crawl_items WHERE session_date = ?1 AND is_consumed = 0 ORDER BY fetched_at ASC",
+            "SELECT c.id, c.category, c.source, c.fetched_at,
+                    (SELECT COUNT(*) FROM crawl_items s WHERE s.source = c.source AND s.is_saved = 1),
+                    c.title || ' ' || COALESCE(c.description, ''),
+                    c.media_duration_secs
+             FROM crawl_items c
+             WHERE c.session_date = ?1 AND c.is_consumed = 0
+             ORDER BY c.fetched_at ASC, c.rowid ASC",
         )?;
-        let pending: Vec<(String, String)> = stmt
+        let pending: Vec<consumption::PendingItem> = stmt
             .query_map(rusqlite::params![today], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                let category: String = row.get(1)?;
+                let source: String = row.get(2)?;
+                let text: String = row.get(5)?;
+                Ok(consumption::PendingItem {
+                    id: row.get(0)?,
+                    cost: costs.cost(&category, &source, &text, row.get(6)?),
+                    category,
+                    source,
+                    fetched_at: row.get(3)?,
+                    engagement: row.get::<_, i64>(4)? as f64,
+                })
             })?
             .collect::<SqlResult<Vec<_>>>()?;
 
@@ -209,20 +683,19 @@ impl Database {
             "consume_start",
             "info",
             &format!(
-                "Starting consumption: budget={:.2}min, pending_items={}",
-                budget_minutes, pending_count
+                "Starting consumption: budget={:.2}min, pending_items={}, strategy={}",
+                budget_minutes,
+                pending_count,
+                strategy.name()
             ),
             None,
             None,
         )?;
 
-        let total_pending_cost: f64 = pending
-            .iter()
-            .map(|(_, cat)| Self::category_cost(cat))
-            .sum();
+        let total_pending_cost: f64 = pending.iter().map(|item| item.cost).sum();
         let min_item_cost = pending
             .iter()
-            .map(|(_, cat)| Self::category_cost(cat))
+            .map(|item| item.cost)
             .fold(f64::INFINITY, f64::min);
         let estimated_max_items = if min_item_cost.is_finite() {
             (budget_minutes / min_item_cost).floor() as i64
@@ -253,19 +726,20 @@ impl Database {
                 None,
                 None,
             )?;
-        } else if budget_minutes < 0.3 {
+        } else if budget_minutes < costs.cheapest() {
             Self::log_diagnostic_with_conn(
                 &conn,
                 "consume_empty",
                 "warn",
-                &format!("items_consumed=0, reason='budget_too_small' - Budget ({:.2}min) smaller than minimum category cost (0.3min)", budget_minutes),
+                &format!(
+                    "items_consumed=0, reason='budget_too_small' - Budget ({:.2}min) smaller than minimum category cost ({:.2}min)",
+                    budget_minutes,
+                    costs.cheapest()
+                ),
                 None,
                 None,
             )?;
-        } else if pending
-            .iter()
-            .all(|(_, cat)| Self::category_cost(cat) > budget_minutes)
-        {
+        } else if pending.iter().all(|item| item.cost > budget_minutes) {
             Self::log_diagnostic_with_conn(
                 &conn,
                 "consume_empty",
@@ -276,63 +750,49 @@ impl Database {
             )?;
         }
 
-        let mut remaining_budget = budget_minutes;
-        let mut consumed_ids: Vec<String> = Vec::new();
-        let mut time_consumed = 0.0_f64;
-        let mut memes: i64 = 0;
-        let mut jokes: i64 = 0;
-        let mut news: i64 = 0;
-        let mut videos: i64 = 0;
-        let mut gossip: i64 = 0;
-
-        for (id, category) in &pending {
-            let cost = Self::category_cost(category);
-            if remaining_budget >= cost {
-                consumed_ids.push(id.clone());
-                remaining_budget -= cost;
-                time_consumed += cost;
-                match category.as_str() {
-                    "meme" => memes += 1,
-                    "joke" => jokes += 1,
-                    "news" => news += 1,
-                    "video" => videos += 1,
-                    "gossip" => gossip += 1,
-                    _ => {}
-                }
-            }
-            // Unconsumed items stay in buffer for next phase - no deletion
-        }
-
-        for id in &consumed_ids {
+        // Unselected items stay in the buffer for the next phase
+        let selected = strategy.select(&pending, budget_minutes);
+
+        let mut result = models::ConsumeResult {
+            items_consumed: selected.len() as i64,
+            items_discarded: (pending_count - selected.len()) as i64,
+            time_consumed_minutes: 0.0,
+            memes_consumed: 0,
+            jokes_consumed: 0,
+            news_consumed: 0,
+            videos_consumed: 0,
+            gossip_consumed: 0,
+            strategy: strategy.name().to_string(),
+        };
+        for &index in &selected {
+            let item = &pending[index];
             conn.execute(
                 "UPDATE crawl_items SET is_consumed = 1 WHERE id = ?1",
-                rusqlite::params![id],
+                rusqlite::params![item.id],
             )?;
+            result.time_consumed_minutes += item.cost;
+            match item.category.as_str() {
+                "meme" => result.memes_consumed += 1,
+                "joke" => result.jokes_consumed += 1,
+                "news" => result.news_consumed += 1,
+                "video" => result.videos_consumed += 1,
+                "gossip" => result.gossip_consumed += 1,
+                _ => {}
+            }
         }
 
-        let unconsumed_count = pending.len() as i64 - consumed_ids.len() as i64;
-
-        Ok(models::ConsumeResult {
-            items_consumed: consumed_ids.len() as i64,
-            items_discarded: unconsumed_count,
-            time_consumed_minutes: time_consumed,
-            memes_consumed: memes,
-            jokes_consumed: jokes,
-            news_consumed: news,
-            videos_consumed: videos,
-            gossip_consumed: gossip,
-        })
-    }
+        // Today's session_stats row only tracks consume calls until it is rolled up
+        conn.execute(
+            "INSERT INTO session_stats (id, date, started_at, consume_calls) VALUES (?1, ?2, ?3, 1)
+             ON CONFLICT(date) DO UPDATE SET consume_calls = consume_calls + 1",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                today,
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+            ],
+        )?;
 
-    fn category_cost(category: &str) -> f64 {
-        match category {
-            "meme" => 0.5,
-            "joke" => 0.3,
-            "news" => 2.0,
-            "video" => 3.0,
-            "gossip" => 1.5,
-            _ => 1.0,
-        }
+        Ok(result)
     }
 
     pub fn mark_item_seen(&self, item_id: &str) -> SqlResult<()> {
@@ -344,20 +804,137 @@ impl Database {
         Ok(())
     }
 
+    /// Flips `is_saved` and adds the item to, or drops it (with its note and
+    /// tags) from, the library.
     pub fn toggle_item_saved(&self, item_id: &str) -> SqlResult<bool> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
             "UPDATE crawl_items SET is_saved = CASE WHEN is_saved = 0 THEN 1 ELSE 0 END WHERE id = ?1",
             rusqlite::params![item_id],
         )?;
-        let saved: bool = conn.query_row(
+        let saved: bool = tx.query_row(
             "SELECT is_saved FROM crawl_items WHERE id = ?1",
             rusqlite::params![item_id],
             |row| row.get(0),
         )?;
+        if saved {
+            let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            tx.execute(
+                "INSERT OR IGNORE INTO library (item_id, saved_at) VALUES (?1, ?2)",
+                rusqlite::params![item_id, now],
+            )?;
+        } else {
+            tx.execute("DELETE FROM library WHERE item_id = ?1", rusqlite::params![item_id])?;
+        }
+        tx.commit()?;
         Ok(saved)
     }
 
+    /// Largest page `get_saved_items` returns.
+    const MAX_LIBRARY_PAGE: i64 = 100;
+
+    /// Saved items, most recently saved first.
+    pub fn get_saved_items(
+        &self,
+        filters: &models::LibraryFilters,
+        paging: &models::Paging,
+    ) -> SqlResult<models::LibraryPage> {
+        let conn = self.conn.lock().unwrap();
+
+        let conditions = "(?1 IS NULL OR c.category = ?1)
+               AND (?2 IS NULL OR EXISTS (SELECT 1 FROM library_tags t WHERE t.item_id = l.item_id AND t.tag = ?2))
+               AND (?3 IS NULL OR l.saved_at >= ?3)
+               AND (?4 IS NULL OR l.saved_at < date(?4, '+1 day'))";
+        let params = rusqlite::params![filters.category, filters.tag, filters.from_date, filters.to_date];
+
+        let total: i64 = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM library l JOIN crawl_items c ON c.id = l.item_id WHERE {}",
+                conditions
+            ),
+            params,
+            |row| row.get(0),
+        )?;
+
+        let columns: Vec<String> = Self::ITEM_COLUMNS.split(", ").map(|c| format!("c.{}", c)).collect();
+        let sql = format!(
+            "SELECT {}, l.saved_at, l.note
+             FROM library l JOIN crawl_items c ON c.id = l.item_id
+             WHERE {}
+             ORDER BY l.saved_at DESC, c.id
+             LIMIT {} OFFSET {}",
+            columns.join(", "),
+            conditions,
+            paging.limit.clamp(1, Self::MAX_LIBRARY_PAGE),
+            paging.offset.max(0)
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut items = stmt
+            .query_map(params, |row| {
+                Ok(models::LibraryItem {
+                    item: Self::row_to_item(row)?,
+                    saved_at: row.get(Self::ITEM_COLUMN_COUNT)?,
+                    note: row.get(Self::ITEM_COLUMN_COUNT + 1)?,
+                    tags: Vec::new(),
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+
+        let mut tag_stmt = conn.prepare("SELECT tag FROM library_tags WHERE item_id = ?1 ORDER BY tag")?;
+        for entry in &mut items {
+            entry.tags = tag_stmt
+                .query_map([&entry.item.id], |row| row.get(0))?
+                .collect::<SqlResult<Vec<_>>>()?;
+        }
+
+        Ok(models::LibraryPage { items, total })
+    }
+
+    /// Returns whether the item is in the library.
+    pub fn set_library_note(&self, item_id: &str, note: Option<&str>) -> SqlResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let note = note.map(str::trim).filter(|n| !n.is_empty());
+        let changed = conn.execute(
+            "UPDATE library SET note = ?1 WHERE item_id = ?2",
+            rusqlite::params![note, item_id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Replaces the item's tags (trimmed, lowercased, deduplicated) and returns
+    /// them, or `None` if the item is not in the library.
+    pub fn set_library_tags(&self, item_id: &str, tags: &[String]) -> SqlResult<Option<Vec<String>>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let in_library: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM library WHERE item_id = ?1)",
+            [item_id],
+            |row| row.get(0),
+        )?;
+        if !in_library {
+            return Ok(None);
+        }
+
+        let mut normalized: Vec<String> = tags
+            .iter()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        normalized.sort();
+        normalized.dedup();
+
+        tx.execute("DELETE FROM library_tags WHERE item_id = ?1", [item_id])?;
+        for tag in &normalized {
+            tx.execute(
+                "INSERT INTO library_tags (item_id, tag) VALUES (?1, ?2)",
+                rusqlite::params![item_id, tag],
+            )?;
+        }
+        tx.commit()?;
+        Ok(Some(normalized))
+    }
+
     pub fn get_latest_unseen_item(&self) -> SqlResult<Option<models::CrawlItem>> {
         let conn = self.conn.lock().unwrap();
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
@@ -373,19 +950,27 @@ impl Database {
         }
     }
 
-    fn estimate_time_saved(stats: &models::DayStats) -> f64 {
-        // Average time a human spends per content type (in minutes)
-        let meme_time = 0.5; // 30 sec per meme
-        let joke_time = 0.3; // 20 sec per joke
-        let news_time = 2.0; // 2 min per news article
-        let video_time = 3.0; // 3 min per video
-        let gossip_time = 1.5; // 1.5 min per gossip item
-
-        (stats.memes_found as f64 * meme_time)
-            + (stats.jokes_found as f64 * joke_time)
-            + (stats.news_checked as f64 * news_time)
-            + (stats.videos_found as f64 * video_time)
-            + (stats.gossip_found as f64 * gossip_time)
+    /// `(category, cost)` of the day's consumed or pending items under `costs`.
+    fn item_costs_with_conn(
+        conn: &Connection,
+        date: &str,
+        consumed: bool,
+        costs: &consumption::CostModel,
+    ) -> SqlResult<Vec<(String, f64)>> {
+        let mut stmt = conn.prepare(
+            "SELECT category, source, title || ' ' || COALESCE(description, ''), media_duration_secs
+             FROM crawl_items WHERE session_date = ?1 AND is_consumed = ?2",
+        )?;
+        let items = stmt
+            .query_map(rusqlite::params![date, consumed], |row| {
+                let category: String = row.get(0)?;
+                let source: String = row.get(1)?;
+                let text: String = row.get(2)?;
+                let cost = costs.cost(&category, &source, &text, row.get(3)?);
+                Ok((category, cost))
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(items)
     }
 
     /// Internal helper that logs a diagnostic event using an already-acquired connection.
@@ -437,23 +1022,124 @@ impl Database {
         Ok(count)
     }
 
+    /// Deletes unconsumed items of earlier days and archives consumed ones:
+    /// their description and thumbnail go. Title and source stay, since
+    /// every earlier day is archived and search must still find "that meme
+    /// from last Tuesday" by them. Returns (deleted, archived).
     pub fn prune_old_items(&self) -> SqlResult<(i64, i64)> {
         let conn = self.conn.lock().unwrap();
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
 
+        // Finished days are rolled up before their items are stripped
+        Self::roll_up_stats_with_conn(&conn)?;
+
+        // Saved items belong to the library and are never pruned
         let deleted = conn.execute(
-            "DELETE FROM crawl_items WHERE session_date < ?1 AND is_consumed = 0",
+            "DELETE FROM crawl_items WHERE session_date < ?1 AND is_consumed = 0 AND is_saved = 0",
             rusqlite::params![today],
         )?;
 
         let stripped = conn.execute(
-            "UPDATE crawl_items SET title = '[ARCHIVED]', description = NULL, thumbnail_url = NULL, is_seen = 1 WHERE session_date < ?1 AND is_consumed = 1",
+            "UPDATE crawl_items SET description = NULL, thumbnail_url = NULL, thumbnail_hash = NULL, is_seen = 1
+             WHERE session_date < ?1 AND is_consumed = 1 AND is_saved = 0",
             rusqlite::params![today],
         )?;
 
+        // Thumbnails of the rows just deleted or stripped are now unreferenced
+        Self::evict_images_with_conn(&conn, images::CACHE_MAX_BYTES)?;
+
         Ok((deleted as i64, stripped as i64))
     }
 
+    /// Adds `image` to the image store and returns its hash. Storing bytes
+    /// that are already there only marks them as used.
+    pub fn store_image(&self, image: &ImageData) -> SqlResult<String> {
+        let conn = self.conn.lock().unwrap();
+        Self::store_image_with_conn(&conn, image)
+    }
+
+    pub(crate) fn store_image_with_conn(conn: &Connection, image: &ImageData) -> SqlResult<String> {
+        let hash = image.hash();
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        conn.execute(
+            "INSERT INTO images (hash, mime, data, size, created_at, last_used_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+             ON CONFLICT(hash) DO UPDATE SET last_used_at = excluded.last_used_at",
+            rusqlite::params![hash, image.mime, image.bytes, image.bytes.len() as i64, now],
+        )?;
+        Ok(hash)
+    }
+
+    /// Points items whose thumbnail was downloaded from `thumbnail_url` at the
+    /// stored image `hash`, e.g. after their thumbnail was evicted and fetched
+    /// again. Returns how many items were relinked.
+    pub fn link_thumbnail(&self, thumbnail_url: &str, hash: &str) -> SqlResult<usize> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE crawl_items SET thumbnail_hash = ?2 WHERE thumbnail_url = ?1 AND thumbnail_hash IS NOT ?2",
+            rusqlite::params![thumbnail_url, hash],
+        )
+    }
+
+    /// The stored image with `hash`, marked as used so eviction keeps it.
+    pub fn get_image(&self, hash: &str) -> SqlResult<Option<ImageData>> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        conn.query_row(
+            "UPDATE images SET last_used_at = ?2 WHERE hash = ?1 RETURNING mime, data",
+            rusqlite::params![hash, now],
+            |row| Ok(ImageData { mime: row.get(0)?, bytes: row.get(1)? }),
+        )
+        .optional()
+    }
+
+    /// Total size in bytes of the image store.
+    pub fn image_store_size(&self) -> SqlResult<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT COALESCE(SUM(size), 0) FROM images", [], |row| row.get(0))
+    }
+
+    /// Drops images no item references, then the least recently used ones
+    /// until the store is within `max_bytes`. Thumbnails of saved items are
+    /// never evicted; items whose thumbnail is evicted fall back to their
+    /// `thumbnail_url`. Returns how many images were removed.
+    pub fn evict_images(&self, max_bytes: i64) -> SqlResult<i64> {
+        let conn = self.conn.lock().unwrap();
+        Self::evict_images_with_conn(&conn, max_bytes)
+    }
+
+    fn evict_images_with_conn(conn: &Connection, max_bytes: i64) -> SqlResult<i64> {
+        let mut removed = conn.execute(
+            "DELETE FROM images WHERE hash NOT IN
+                 (SELECT thumbnail_hash FROM crawl_items WHERE thumbnail_hash IS NOT NULL)",
+            [],
+        )? as i64;
+
+        let mut total: i64 = conn.query_row("SELECT COALESCE(SUM(size), 0) FROM images", [], |row| row.get(0))?;
+        if total <= max_bytes {
+            return Ok(removed);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT hash, size FROM images WHERE hash NOT IN
+                 (SELECT thumbnail_hash FROM crawl_items WHERE is_saved = 1 AND thumbnail_hash IS NOT NULL)
+             ORDER BY last_used_at, rowid",
+        )?;
+        let candidates = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+            .collect::<SqlResult<Vec<_>>>()?;
+        for (hash, size) in candidates {
+            if total <= max_bytes {
+                break;
+            }
+            conn.execute("UPDATE crawl_items SET thumbnail_hash = NULL WHERE thumbnail_hash = ?1", [&hash])?;
+            conn.execute("DELETE FROM images WHERE hash = ?1", [&hash])?;
+            total -= size;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
     pub fn get_last_active_timestamp(&self) -> SqlResult<chrono::DateTime<chrono::Utc>> {
         let conn = self.conn.lock().unwrap();
 
@@ -489,6 +1175,32 @@ impl Database {
         Ok(())
     }
 
+    /// Every `app_state` entry, ordered by key.
+    pub fn list_app_state(&self) -> SqlResult<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, CAST(value AS TEXT) FROM app_state ORDER BY key")?;
+        let entries = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    pub fn get_app_state(&self, key: &str) -> SqlResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT CAST(value AS TEXT) FROM app_state WHERE key = ?1")?;
+        let mut rows = stmt.query_map(rusqlite::params![key], |row| row.get::<_, String>(0))?;
+        rows.next().transpose()
+    }
+
+    pub fn set_app_state(&self, key: &str, value: &str) -> SqlResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO app_state (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            rusqlite::params![key, value],
+        )?;
+        Ok(())
+    }
+
     pub fn get_diagnostic_summary(&self) -> SqlResult<models::DiagnosticSummary> {
         let conn = self.conn.lock().unwrap();
         let today = chrono::Local::now().format("%Y-%m-%d").to_string();
@@ -499,22 +1211,16 @@ impl Database {
             |row| row.get(0),
         )?;
 
-        let mut stmt = conn.prepare(
-            "SELECT category FROM crawl_items WHERE session_date = ?1 AND is_consumed = 0",
-        )?;
-        let categories: Vec<String> = stmt
-            .query_map(rusqlite::params![today], |row| row.get::<_, String>(0))?
-            .collect::<SqlResult<Vec<_>>>()?;
+        let pending = Self::item_costs_with_conn(&conn, &today, false, &consumption::CostModel::current())?;
 
         let mut total_cost: f64 = 0.0;
         let mut min_cost = f64::INFINITY;
         let mut max_cost: f64 = 0.0;
 
-        for category in &categories {
-            let cost = Self::category_cost(category);
+        for (_, cost) in &pending {
             total_cost += cost;
-            min_cost = min_cost.min(cost);
-            max_cost = max_cost.max(cost);
+            min_cost = min_cost.min(*cost);
+            max_cost = max_cost.max(*cost);
         }
 
         if min_cost.is_infinite() {
@@ -587,118 +1293,237 @@ impl Database {
         Ok(deleted as i64)
     }
 
-    pub fn get_provider_status(&self) -> SqlResult<Vec<models::ProviderStatus>> {
+    pub fn record_provider_run(&self, run: &models::ProviderRun) -> SqlResult<()> {
         let conn = self.conn.lock().unwrap();
-        let events = Self::fetch_recent_crawl_events(&conn)?;
-        let statuses = Self::derive_provider_statuses(&conn, &events)?;
-        Ok(statuses)
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO provider_runs (id, provider, started_at, duration_ms, outcome, items_fetched, items_new, error, status_code)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                id,
+                run.provider,
+                run.started_at,
+                run.duration_ms,
+                run.outcome,
+                run.items_fetched,
+                run.items_new,
+                run.error,
+                run.status_code,
+            ],
+        )?;
+        Ok(())
     }
 
-    fn fetch_recent_crawl_events(
-        conn: &Connection,
-    ) -> SqlResult<Vec<(String, String, String, String)>> {
-        let sql = "SELECT
-                event_type,
-                message,
-                timestamp,
-                severity
-             FROM diagnostic_logs
-             WHERE event_type IN ('crawl_start', 'crawl_success', 'crawl_error', 'provider_fetch')
-             AND timestamp > datetime('now', '-1 day')
-             ORDER BY timestamp DESC";
-
-        let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
-                row.get::<_, String>(3)?,
-            ))
-        })?;
+    pub fn add_source(&self, kind: &str, target: &str, category: &str) -> SqlResult<models::Source> {
+        let conn = self.conn.lock().unwrap();
+        let source = models::Source {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            target: target.to_string(),
+            category: category.to_string(),
+            enabled: true,
+            created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        conn.execute(
+            "INSERT INTO sources (id, kind, target, category, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            rusqlite::params![source.id, source.kind, source.target, source.category, source.created_at],
+        )?;
+        Ok(source)
+    }
+
+    pub fn list_sources(&self) -> SqlResult<Vec<models::Source>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, target, category, enabled, created_at
+             FROM sources
+             ORDER BY created_at, rowid",
+        )?;
+        let sources = stmt
+            .query_map([], |row| {
+                Ok(models::Source {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    target: row.get(2)?,
+                    category: row.get(3)?,
+                    enabled: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(sources)
+    }
 
-        let events: SqlResult<Vec<_>> = rows.collect();
-        events
+    /// Returns whether a source with that id existed.
+    pub fn remove_source(&self, id: &str) -> SqlResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute("DELETE FROM sources WHERE id = ?1", [id])?;
+        Ok(changed > 0)
     }
 
-    fn derive_provider_statuses(
-        conn: &Connection,
-        events: &[(String, String, String, String)],
-    ) -> SqlResult<Vec<models::ProviderStatus>> {
-        let provider_names = vec![
-            ("google-news", "news"),
-            ("dadjokes", "joke"),
-            ("reddit-memes", "meme"),
-            ("entertainment", "gossip"),
-            ("memes", "meme"),
-            ("gossip", "gossip"),
-            ("reddit-videos", "video"),
-            ("icanhazdadjoke", "joke"),
-            ("jokeapi", "joke"),
-            ("uselessfacts", "joke"),
-            ("chucknorris", "joke"),
-            ("hackernews", "news"),
-            ("bbc-news", "news"),
-        ];
+    /// Returns whether a source with that id existed.
+    pub fn set_source_enabled(&self, id: &str, enabled: bool) -> SqlResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE sources SET enabled = ?1 WHERE id = ?2",
+            rusqlite::params![enabled as i32, id],
+        )?;
+        Ok(changed > 0)
+    }
 
-        let mut statuses: Vec<models::ProviderStatus> = Vec::new();
+    pub fn add_filter_rule(&self, kind: &str, pattern: &str) -> SqlResult<models::FilterRule> {
+        let conn = self.conn.lock().unwrap();
+        let rule = models::FilterRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            pattern: pattern.to_string(),
+            enabled: true,
+            hit_count: 0,
+            created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+        conn.execute(
+            "INSERT INTO filter_rules (id, kind, pattern, enabled, hit_count, created_at)
+             VALUES (?1, ?2, ?3, 1, 0, ?4)",
+            rusqlite::params![rule.id, rule.kind, rule.pattern, rule.created_at],
+        )?;
+        Ok(rule)
+    }
+
+    pub fn list_filter_rules(&self) -> SqlResult<Vec<models::FilterRule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, pattern, enabled, hit_count, created_at
+             FROM filter_rules
+             ORDER BY created_at, rowid",
+        )?;
+        let rules = stmt
+            .query_map([], |row| {
+                Ok(models::FilterRule {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    pattern: row.get(2)?,
+                    enabled: row.get(3)?,
+                    hit_count: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(rules)
+    }
+
+    /// Returns whether a rule with that id existed.
+    pub fn remove_filter_rule(&self, id: &str) -> SqlResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute("DELETE FROM filter_rules WHERE id = ?1", [id])?;
+        Ok(changed > 0)
+    }
+
+    /// Returns whether a rule with that id existed.
+    pub fn set_filter_rule_enabled(&self, id: &str, enabled: bool) -> SqlResult<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE filter_rules SET enabled = ?1 WHERE id = ?2",
+            rusqlite::params![enabled as i32, id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    pub fn record_filter_hit(&self, id: &str) -> SqlResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("UPDATE filter_rules SET hit_count = hit_count + 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Number of most recent runs per provider that health figures are computed over.
+    const PROVIDER_HEALTH_WINDOW: i64 = 50;
+
+    pub fn get_provider_status(&self) -> SqlResult<Vec<models::ProviderStatus>> {
+        let registry = crate::crawler::registry::ProviderRegistry::load(self);
+        let conn = self.conn.lock().unwrap();
 
-        for (name, category) in provider_names {
-            let status = Self::derive_single_provider_status(conn, events, name, category)?;
-            statuses.push(status);
+        let mut statuses: Vec<models::ProviderStatus> = Vec::new();
+        for (name, category) in registry.descriptors() {
+            let runs = Self::recent_provider_runs(&conn, &name)?;
+            statuses.push(Self::derive_provider_status(&name, &category, &runs));
         }
 
         Ok(statuses)
     }
 
-    fn derive_single_provider_status(
-        conn: &Connection,
-        events: &[(String, String, String, String)],
+    /// Most recent runs for a provider, newest first.
+    fn recent_provider_runs(conn: &Connection, provider: &str) -> SqlResult<Vec<models::ProviderRun>> {
+        let mut stmt = conn.prepare(
+            "SELECT provider, started_at, duration_ms, outcome, items_fetched, items_new, error, status_code
+             FROM provider_runs
+             WHERE provider = ?1
+             ORDER BY started_at DESC
+             LIMIT ?2",
+        )?;
+        let runs = stmt
+            .query_map(rusqlite::params![provider, Self::PROVIDER_HEALTH_WINDOW], |row| {
+                Ok(models::ProviderRun {
+                    provider: row.get(0)?,
+                    started_at: row.get(1)?,
+                    duration_ms: row.get(2)?,
+                    outcome: row.get(3)?,
+                    items_fetched: row.get(4)?,
+                    items_new: row.get(5)?,
+                    error: row.get(6)?,
+                    status_code: row.get(7)?,
+                })
+            })?
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(runs)
+    }
+
+    fn derive_provider_status(
         name: &str,
         category: &str,
-    ) -> SqlResult<models::ProviderStatus> {
-        let mut last_status = "unknown".to_string();
-        let mut last_timestamp: Option<String> = None;
-        let mut error_count = 0;
-
-        for (event_type, message, timestamp, severity) in events {
-            if message.to_lowercase().contains(name) {
-                if last_timestamp.is_none() {
-                    last_timestamp = Some(timestamp.clone());
-                }
-
-                if event_type == "crawl_success" {
-                    last_status = "ok".to_string();
-                    break;
-                } else if severity == "error" {
-                    error_count += 1;
-                    if last_status != "ok" {
-                        last_status = "error".to_string();
-                    }
-                }
-            }
-        }
+        runs: &[models::ProviderRun],
+    ) -> models::ProviderStatus {
+        let deferred = runs.first().is_some_and(|r| r.is_deferred());
+        // Deferred runs never reached the network, so they don't count towards health
+        let runs: Vec<&models::ProviderRun> = runs.iter().filter(|r| !r.is_deferred()).collect();
+        let latest = runs.first();
+        let last_failure = runs.iter().find(|r| !r.is_success());
+
+        let last_fetch_status = match latest {
+            _ if deferred => "deferred",
+            None => "unknown",
+            Some(r) if r.is_success() => "ok",
+            Some(r) if r.outcome == "empty" => "empty",
+            Some(_) => "error",
+        };
 
-        if last_status == "unknown" {
-            let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-            let has_items: bool = conn.query_row(
-                "SELECT EXISTS(SELECT 1 FROM crawl_items WHERE session_date = ?1 AND category = ?2 LIMIT 1)",
-                rusqlite::params![today, category],
-                |row| row.get(0),
-            ).unwrap_or(false);
+        let successes = runs.iter().filter(|r| r.is_success()).count();
+        let success_rate = if runs.is_empty() {
+            0.0
+        } else {
+            successes as f64 / runs.len() as f64
+        };
 
-            if has_items {
-                last_status = "ok".to_string();
-                last_timestamp = Some(today);
-            }
-        }
+        let mut durations: Vec<i64> = runs.iter().map(|r| r.duration_ms).collect();
+        durations.sort_unstable();
+        let median_latency_ms = match durations.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(durations[n / 2]),
+            n => Some((durations[n / 2 - 1] + durations[n / 2]) / 2),
+        };
 
-        Ok(models::ProviderStatus {
+        models::ProviderStatus {
             provider_name: name.to_string(),
             category: category.to_string(),
-            last_fetch_status: last_status,
-            last_fetch_timestamp: last_timestamp,
-            recent_error_count: error_count,
-        })
+            last_fetch_status: last_fetch_status.to_string(),
+            last_fetch_timestamp: latest.map(|r| r.started_at.clone()),
+            recent_error_count: (runs.len() - successes) as i64,
+            last_error: last_failure.and_then(|r| r.error.clone()),
+            last_error_kind: last_failure.map(|r| r.outcome.clone()),
+            last_status_code: last_failure.and_then(|r| r.status_code),
+            last_latency_ms: latest.map(|r| r.duration_ms as u64),
+            total_runs: runs.len() as i64,
+            success_rate,
+            median_latency_ms,
+            consecutive_failures: runs.iter().take_while(|r| !r.is_success()).count() as i64,
+        }
     }
 }
@@ -8,13 +8,123 @@ pub struct CrawlItem {
     pub title: String,
     pub url: String,
     pub thumbnail_url: Option<String>,
-    pub thumbnail_data: Option<String>,
+    /// Key of the downloaded thumbnail in the image store, if it was
+    /// downloaded and not evicted since.
+    pub thumbnail_hash: Option<String>,
     pub description: Option<String>,
     pub fetched_at: String,
     pub is_seen: bool,
     pub is_saved: bool,
     pub is_consumed: bool,
     pub session_date: String,
+    #[serde(default)]
+    pub metadata: ItemMetadata,
+}
+
+/// Engagement and provenance details a provider knew about an item. Every
+/// field is optional; providers fill in what their API exposes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemMetadata {
+    /// Upvotes or points.
+    pub score: Option<i64>,
+    pub comments: Option<i64>,
+    pub author: Option<String>,
+    /// When the item was posted upstream, `YYYY-MM-DD HH:MM:SS` local time.
+    pub published_at: Option<String>,
+    pub media_duration_secs: Option<f64>,
+    /// Host of the linked content, e.g. `github.com`.
+    pub domain: Option<String>,
+}
+
+/// What `insert_item_deduped` did with an item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertOutcome {
+    Inserted,
+    /// The item, or a copy already linked to a canonical item, was stored before.
+    AlreadyStored,
+    /// Not stored; recorded as a copy of `canonical_id`.
+    Duplicate { canonical_id: String, reason: String },
+}
+
+/// A crawled copy of a stored item that was not stored itself, from the
+/// `item_duplicates` table. `reason` is `url`, `title` or `image`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemDuplicate {
+    pub id: String,
+    pub canonical_id: String,
+    pub source: String,
+    pub title: String,
+    pub url: String,
+    pub reason: String,
+    pub similarity: f64,
+    pub detected_at: String,
+}
+
+/// Optional restrictions for `search_items`; dates are `session_date`s (YYYY-MM-DD).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub category: Option<String>,
+    pub source: Option<String>,
+    pub saved_only: bool,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Paging {
+    pub offset: i64,
+    pub limit: i64,
+}
+
+impl Default for Paging {
+    fn default() -> Self {
+        Self { offset: 0, limit: 20 }
+    }
+}
+
+/// A search match. `title_highlighted` and `snippet` are HTML: the text is
+/// escaped and matched terms are wrapped in `<mark>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub item: CrawlItem,
+    pub title_highlighted: String,
+    pub snippet: String,
+    /// BM25 score; lower is a better match.
+    pub rank: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub total: i64,
+}
+
+/// A saved item with its library metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryItem {
+    pub item: CrawlItem,
+    pub saved_at: String,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Optional restrictions for `get_saved_items`; dates (YYYY-MM-DD, inclusive)
+/// apply to `saved_at`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryFilters {
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryPage {
+    pub items: Vec<LibraryItem>,
+    pub total: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -28,6 +138,40 @@ pub struct DayStats {
     pub estimated_time_saved_minutes: f64,
 }
 
+/// One day of the stats history.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DailyStats {
+    pub date: String,
+    pub stats: DayStats,
+    /// Distinct sources among the day's consumed items.
+    pub providers_used: i64,
+    pub consume_calls: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsPeriod {
+    /// Today and the 6 days before it.
+    Week,
+    /// Today and the 29 days before it.
+    Month,
+    /// Everything since the first recorded day.
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub period: StatsPeriod,
+    pub from_date: String,
+    pub to_date: String,
+    /// Days with at least one consumed item.
+    pub days_active: i64,
+    pub totals: DayStats,
+    pub consume_calls: i64,
+    pub average_items_per_day: f64,
+    pub average_providers_per_day: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsumeResult {
     pub items_consumed: i64,
@@ -38,6 +182,8 @@ pub struct ConsumeResult {
     pub news_consumed: i64,
     pub videos_consumed: i64,
     pub gossip_consumed: i64,
+    /// Name of the consumption strategy that picked the items.
+    pub strategy: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +226,63 @@ pub struct ProviderStatus {
     pub last_fetch_status: String,
     pub last_fetch_timestamp: Option<String>,
     pub recent_error_count: i64,
+    pub last_error: Option<String>,
+    pub last_error_kind: Option<String>,
+    pub last_status_code: Option<u16>,
+    pub last_latency_ms: Option<u64>,
+    pub total_runs: i64,
+    pub success_rate: f64,
+    pub median_latency_ms: Option<i64>,
+    pub consecutive_failures: i64,
+}
+
+/// One invocation of a provider's `fetch`, as stored in `provider_runs`.
+/// `outcome` is `"success"`, `"deferred"` (not fetched because a host's
+/// request budget was spent) or the `FetchError` kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderRun {
+    pub provider: String,
+    pub started_at: String,
+    pub duration_ms: i64,
+    pub outcome: String,
+    pub items_fetched: i64,
+    pub items_new: i64,
+    pub error: Option<String>,
+    pub status_code: Option<u16>,
+}
+
+impl ProviderRun {
+    pub fn is_success(&self) -> bool {
+        self.outcome == "success"
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        self.outcome == "deferred"
+    }
+}
+
+/// A user-defined content source from the `sources` table.
+/// `kind` is one of `crawler::sources::SOURCE_KINDS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    pub id: String,
+    pub kind: String,
+    pub target: String,
+    pub category: String,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+/// A mute rule from the `filter_rules` table.
+/// `kind` is one of `crawler::filters::FILTER_KINDS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterRule {
+    pub id: String,
+    pub kind: String,
+    pub pattern: String,
+    pub enabled: bool,
+    pub hit_count: i64,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
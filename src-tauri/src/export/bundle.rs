@@ -0,0 +1,414 @@
+use rusqlite::{OptionalExtension, Result as SqlResult, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::crawler::provider::generate_stable_id;
+use crate::crawler::url::canonicalize;
+use crate::crawler::{filters, sources};
+use crate::db::migrations;
+use crate::db::models::{DailyStats, FilterRule, LibraryFilters, LibraryItem, Source};
+use crate::db::Database;
+use crate::images::ImageData;
+use crate::settings::Settings;
+
+/// Value of `Bundle::format`, so other JSON files are rejected early.
+pub const BUNDLE_FORMAT: &str = "cazzmachine-bundle";
+/// Bumped whenever the bundle layout changes incompatibly.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// `app_state` keys that describe this machine rather than the user, and so
+/// are neither exported nor imported.
+const LOCAL_STATE_KEYS: [&str; 1] = ["last_active_timestamp"];
+
+/// A portable backup of everything worth moving between machines: settings,
+/// the saved library with its thumbnails, sources, mute rules and the stats of
+/// finished days. Crawl history is not included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: String,
+    pub bundle_version: u32,
+    /// Schema version of the database the bundle was made from.
+    pub schema_version: i32,
+    pub created_at: String,
+    pub app_state: BTreeMap<String, String>,
+    pub saved: Vec<LibraryItem>,
+    /// Thumbnails of `saved` as data URLs, keyed by `thumbnail_hash`.
+    #[serde(default)]
+    pub images: BTreeMap<String, String>,
+    pub sources: Vec<Source>,
+    pub filters: Vec<FilterRule>,
+    pub daily_stats: Vec<DailyStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSummary {
+    pub path: String,
+    pub created_at: String,
+    pub schema_version: i32,
+    pub settings: usize,
+    pub saved_items: usize,
+    pub sources: usize,
+    pub filters: usize,
+    pub days: usize,
+}
+
+/// What an import did, or would do in a dry run, per section.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportCounts {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Entries that are invalid in this build and were left out.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub settings: ImportCounts,
+    pub saved: ImportCounts,
+    pub sources: ImportCounts,
+    pub filters: ImportCounts,
+    pub daily_stats: ImportCounts,
+}
+
+pub fn create(db: &Database) -> Result<Bundle, String> {
+    let schema_version = migrations::current_version(&db.conn.lock().unwrap()).map_err(|e| e.to_string())?;
+
+    let app_state = db
+        .list_app_state()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|(key, _)| !LOCAL_STATE_KEYS.contains(&key.as_str()))
+        .collect();
+
+    // Today is still in progress, so only finished days travel
+    let yesterday = chrono::Local::now().date_naive() - chrono::Duration::days(1);
+    let daily_stats = db
+        .get_stats_between(None, Some(yesterday))
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|d| d.stats.total_items > 0 || d.consume_calls > 0)
+        .collect();
+
+    let saved = super::all_saved_items(db, &LibraryFilters::default())?;
+    let mut images = BTreeMap::new();
+    for hash in saved.iter().filter_map(|s| s.item.thumbnail_hash.as_ref()) {
+        if let Some(image) = db.get_image(hash).map_err(|e| e.to_string())? {
+            images.insert(hash.clone(), image.to_data_url());
+        }
+    }
+
+    Ok(Bundle {
+        format: BUNDLE_FORMAT.to_string(),
+        bundle_version: BUNDLE_VERSION,
+        schema_version,
+        created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        app_state,
+        saved,
+        images,
+        sources: db.list_sources().map_err(|e| e.to_string())?,
+        filters: db.list_filter_rules().map_err(|e| e.to_string())?,
+        daily_stats,
+    })
+}
+
+pub fn write(db: &Database, dest: &Path) -> Result<BundleSummary, String> {
+    let bundle = create(db)?;
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(dest, json).map_err(|e| format!("Cannot write {}: {}", dest.display(), e))?;
+
+    Ok(BundleSummary {
+        path: dest.to_string_lossy().into_owned(),
+        created_at: bundle.created_at,
+        schema_version: bundle.schema_version,
+        settings: bundle.app_state.len(),
+        saved_items: bundle.saved.len(),
+        sources: bundle.sources.len(),
+        filters: bundle.filters.len(),
+        days: bundle.daily_stats.len(),
+    })
+}
+
+pub fn read(path: &Path) -> Result<Bundle, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let bundle: Bundle = serde_json::from_str(&json).map_err(|e| format!("Not a valid bundle: {}", e))?;
+
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(format!("Not a cazzmachine bundle (format '{}')", bundle.format));
+    }
+    if bundle.bundle_version > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle version {} is newer than this build supports ({})",
+            bundle.bundle_version, BUNDLE_VERSION
+        ));
+    }
+    if bundle.schema_version > migrations::latest_version() {
+        return Err(format!(
+            "Bundle was made with a newer database schema (v{}); update the app first",
+            bundle.schema_version
+        ));
+    }
+    Ok(bundle)
+}
+
+/// Merges `bundle` into the database. Nothing local is overwritten except
+/// settings, of which only the keys `Settings` knows are taken, clamped like
+/// any other settings change: existing items keep their note unless they have none, tags are
+/// unioned, and sources, rules and days already present are left alone.
+/// Items are matched by `generate_stable_id` of their canonical URL, the
+/// way the crawler assigns ids.
+///
+/// With `dry_run` the merge runs in a transaction that is rolled back, so the
+/// report is exactly what a real import would do.
+pub fn import(db: &Database, bundle: &Bundle, dry_run: bool) -> Result<ImportReport, String> {
+    db.with_transaction(!dry_run, |tx| {
+        // Finished local days need their rollup before imported days can be told apart
+        Database::roll_up_stats_with_conn(tx)?;
+
+        Ok(ImportReport {
+            dry_run,
+            settings: import_settings(tx, &bundle.app_state)?,
+            saved: import_saved(tx, &bundle.saved, &bundle.images)?,
+            sources: import_sources(tx, &bundle.sources)?,
+            filters: import_filters(tx, &bundle.filters)?,
+            daily_stats: import_stats(tx, &bundle.daily_stats)?,
+        })
+    })
+    .map_err(|e| e.to_string())
+}
+
+fn import_settings(tx: &Transaction, app_state: &BTreeMap<String, String>) -> SqlResult<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    let mut stored = BTreeMap::new();
+    let mut settings = Settings::default();
+    for key in Settings::KEYS {
+        let value: Option<String> = tx
+            .query_row("SELECT CAST(value AS TEXT) FROM app_state WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?;
+        if let Some(value) = value {
+            settings.set(key, &value);
+            stored.insert(key, value);
+        }
+    }
+
+    // Unknown keys, local state and values that don't parse stay behind
+    let mut imported = Vec::new();
+    for (key, value) in app_state {
+        if settings.set(key, value) {
+            imported.push(key.as_str());
+        } else {
+            counts.skipped += 1;
+        }
+    }
+
+    for (key, value) in settings.clamped().entries() {
+        if !imported.contains(&key) {
+            continue;
+        }
+        match stored.get(key) {
+            Some(current) if *current == value => {
+                counts.unchanged += 1;
+                continue;
+            }
+            Some(_) => counts.updated += 1,
+            None => counts.added += 1,
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO app_state (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            rusqlite::params![key, value],
+        )?;
+    }
+    Ok(counts)
+}
+
+fn import_saved(
+    tx: &Transaction,
+    saved: &[LibraryItem],
+    images: &BTreeMap<String, String>,
+) -> SqlResult<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    for entry in saved {
+        let item = &entry.item;
+        if item.url.trim().is_empty() || !sources::CATEGORIES.contains(&item.category.as_str()) {
+            counts.skipped += 1;
+            continue;
+        }
+        let url = canonicalize(&item.url);
+        let id = generate_stable_id(&url);
+        let is_saved: Option<bool> = tx
+            .query_row("SELECT is_saved FROM crawl_items WHERE id = ?1", [&id], |row| row.get(0))
+            .optional()?;
+
+        match is_saved {
+            None => {
+                let thumbnail = item
+                    .thumbnail_hash
+                    .as_ref()
+                    .and_then(|hash| images.get(hash))
+                    .and_then(|data_url| ImageData::from_data_url(data_url));
+                let thumbnail_hash = match &thumbnail {
+                    Some(image) => Some(Database::store_image_with_conn(tx, image)?),
+                    None => None,
+                };
+                let inserted = tx.execute(
+                    "INSERT OR IGNORE INTO crawl_items (id, source, category, title, url, thumbnail_url, thumbnail_hash, description, fetched_at, is_seen, is_saved, is_consumed, session_date,
+                                                        score, comments, author, published_at, media_duration_secs, domain)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 1, 1, 1, ?10, ?11, ?12, ?13, ?14, ?15, ?16)",
+                    rusqlite::params![
+                        id,
+                        item.source,
+                        item.category,
+                        item.title,
+                        url,
+                        item.thumbnail_url,
+                        thumbnail_hash,
+                        item.description,
+                        item.fetched_at,
+                        item.session_date,
+                        item.metadata.score,
+                        item.metadata.comments,
+                        item.metadata.author,
+                        item.metadata.published_at,
+                        item.metadata.media_duration_secs,
+                        item.metadata.domain,
+                    ],
+                )?;
+                if inserted == 0 {
+                    // The URL is already stored under a different id
+                    counts.skipped += 1;
+                    continue;
+                }
+            }
+            Some(false) => {
+                tx.execute("UPDATE crawl_items SET is_saved = 1 WHERE id = ?1", [&id])?;
+            }
+            Some(true) => {}
+        }
+
+        let mut changed = tx.execute(
+            "INSERT OR IGNORE INTO library (item_id, saved_at, note) VALUES (?1, ?2, ?3)",
+            rusqlite::params![id, entry.saved_at, entry.note],
+        )? > 0;
+        if let Some(note) = entry.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            changed |= tx.execute(
+                "UPDATE library SET note = ?1 WHERE item_id = ?2 AND note IS NULL",
+                rusqlite::params![note, id],
+            )? > 0;
+        }
+        for tag in &entry.tags {
+            let tag = tag.trim().to_lowercase();
+            if !tag.is_empty() {
+                changed |= tx.execute(
+                    "INSERT OR IGNORE INTO library_tags (item_id, tag) VALUES (?1, ?2)",
+                    rusqlite::params![id, tag],
+                )? > 0;
+            }
+        }
+
+        match is_saved {
+            None => counts.added += 1,
+            Some(false) => counts.updated += 1,
+            Some(true) if changed => counts.updated += 1,
+            Some(true) => counts.unchanged += 1,
+        }
+    }
+    Ok(counts)
+}
+
+fn import_sources(tx: &Transaction, bundle_sources: &[Source]) -> SqlResult<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    for source in bundle_sources {
+        let Ok(target) = sources::normalize(&source.kind, &source.target, &source.category) else {
+            counts.skipped += 1;
+            continue;
+        };
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO sources (id, kind, target, category, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                source.kind,
+                target,
+                source.category,
+                source.enabled,
+                source.created_at
+            ],
+        )?;
+        if inserted > 0 {
+            counts.added += 1;
+        } else {
+            counts.unchanged += 1;
+        }
+    }
+    Ok(counts)
+}
+
+fn import_filters(tx: &Transaction, rules: &[FilterRule]) -> SqlResult<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    for rule in rules {
+        let Ok(pattern) = filters::normalize(&rule.kind, &rule.pattern) else {
+            counts.skipped += 1;
+            continue;
+        };
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO filter_rules (id, kind, pattern, enabled, hit_count, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                rule.kind,
+                pattern,
+                rule.enabled,
+                rule.hit_count,
+                rule.created_at
+            ],
+        )?;
+        if inserted > 0 {
+            counts.added += 1;
+        } else {
+            counts.unchanged += 1;
+        }
+    }
+    Ok(counts)
+}
+
+fn import_stats(tx: &Transaction, days: &[DailyStats]) -> SqlResult<ImportCounts> {
+    let mut counts = ImportCounts::default();
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    for day in days {
+        let valid_date = chrono::NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").is_ok();
+        if !valid_date || day.date >= today {
+            counts.skipped += 1;
+            continue;
+        }
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO session_stats (id, date, started_at, memes_found, jokes_found, news_checked, videos_found, gossip_found, total_items, estimated_time_saved_minutes, providers_used, consume_calls, rolled_up_at)
+             VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                uuid::Uuid::new_v4().to_string(),
+                day.date,
+                day.stats.memes_found,
+                day.stats.jokes_found,
+                day.stats.news_checked,
+                day.stats.videos_found,
+                day.stats.gossip_found,
+                day.stats.total_items,
+                day.stats.estimated_time_saved_minutes,
+                day.providers_used,
+                day.consume_calls,
+                now
+            ],
+        )?;
+        if inserted > 0 {
+            counts.added += 1;
+        } else {
+            counts.unchanged += 1;
+        }
+    }
+    Ok(counts)
+}
@@ -0,0 +1,338 @@
+use crate::crawler::sources::CATEGORIES;
+use crate::db::models::{CrawlItem, DailyStats, LibraryFilters, LibraryItem, Paging};
+use crate::db::Database;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub mod bundle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    /// One file per table, named after the destination:
+    /// `<stem>-saved.csv`, `<stem>-history.csv` and `<stem>-stats.csv`.
+    Csv,
+    /// A human-readable digest grouped by category.
+    Markdown,
+}
+
+/// Everything an export contains. Dates are inclusive YYYY-MM-DD bounds on
+/// `saved_at` for saved items, `session_date` for history and the day for
+/// stats. Thumbnails are left out to keep exports small.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportData {
+    pub generated_at: String,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub saved: Vec<LibraryItem>,
+    pub history: Vec<CrawlItem>,
+    pub daily_stats: Vec<DailyStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportReport {
+    pub format: ExportFormat,
+    pub files: Vec<String>,
+    pub saved_items: usize,
+    pub history_items: usize,
+    pub days: usize,
+}
+
+fn parse_date(date: Option<&str>) -> Result<Option<chrono::NaiveDate>, String> {
+    date.map(|d| {
+        chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", d))
+    })
+    .transpose()
+}
+
+pub fn collect(db: &Database, from_date: Option<&str>, to_date: Option<&str>) -> Result<ExportData, String> {
+    let from = parse_date(from_date)?;
+    let to = parse_date(to_date)?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(format!("Start date {} is after end date {}", from, to));
+        }
+    }
+
+    let filters = LibraryFilters {
+        from_date: from_date.map(str::to_string),
+        to_date: to_date.map(str::to_string),
+        ..Default::default()
+    };
+    let mut saved = all_saved_items(db, &filters)?;
+
+    let mut history = db
+        .get_consumed_items(from_date, to_date)
+        .map_err(|e| e.to_string())?;
+    let daily_stats = db.get_stats_between(from, to).map_err(|e| e.to_string())?;
+
+    for item in saved.iter_mut().map(|s| &mut s.item).chain(history.iter_mut()) {
+        item.thumbnail_hash = None;
+    }
+
+    Ok(ExportData {
+        generated_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        from_date: from_date.map(str::to_string),
+        to_date: to_date.map(str::to_string),
+        saved,
+        history,
+        daily_stats,
+    })
+}
+
+/// Every saved item matching `filters`, most recently saved first.
+fn all_saved_items(db: &Database, filters: &LibraryFilters) -> Result<Vec<LibraryItem>, String> {
+    let mut saved = Vec::new();
+    loop {
+        let paging = Paging {
+            offset: saved.len() as i64,
+            limit: 100,
+        };
+        let page = db.get_saved_items(filters, &paging).map_err(|e| e.to_string())?;
+        let done = page.items.is_empty() || saved.len() + page.items.len() >= page.total as usize;
+        saved.extend(page.items);
+        if done {
+            return Ok(saved);
+        }
+    }
+}
+
+/// Writes an export of the given range to `dest` and returns what was written.
+pub fn export(
+    db: &Database,
+    format: ExportFormat,
+    dest: &Path,
+    from_date: Option<&str>,
+    to_date: Option<&str>,
+) -> Result<ExportReport, String> {
+    let data = collect(db, from_date, to_date)?;
+
+    let files: Vec<(PathBuf, String)> = match format {
+        ExportFormat::Json => vec![(
+            dest.to_path_buf(),
+            serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?,
+        )],
+        ExportFormat::Csv => vec![
+            (sibling(dest, "saved"), saved_csv(&data.saved)),
+            (sibling(dest, "history"), history_csv(&data.history)),
+            (sibling(dest, "stats"), stats_csv(&data.daily_stats)),
+        ],
+        ExportFormat::Markdown => vec![(dest.to_path_buf(), markdown_digest(&data))],
+    };
+
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+    }
+    for (path, contents) in &files {
+        std::fs::write(path, contents).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    }
+
+    Ok(ExportReport {
+        format,
+        files: files.iter().map(|(p, _)| p.to_string_lossy().into_owned()).collect(),
+        saved_items: data.saved.len(),
+        history_items: data.history.len(),
+        days: data.daily_stats.len(),
+    })
+}
+
+/// `dir/export.csv` + `saved` -> `dir/export-saved.csv`.
+fn sibling(dest: &Path, table: &str) -> PathBuf {
+    let stem = dest.file_stem().map_or("cazzmachine".into(), |s| s.to_string_lossy());
+    dest.with_file_name(format!("{}-{}.csv", stem, table))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_rows(header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut out = header.join(",");
+    out.push_str("\r\n");
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+pub fn saved_csv(saved: &[LibraryItem]) -> String {
+    csv_rows(
+        &["saved_at", "category", "source", "title", "url", "note", "tags"],
+        saved.iter().map(|s| {
+            vec![
+                s.saved_at.clone(),
+                s.item.category.clone(),
+                s.item.source.clone(),
+                s.item.title.clone(),
+                s.item.url.clone(),
+                s.note.clone().unwrap_or_default(),
+                s.tags.join(";"),
+            ]
+        }),
+    )
+}
+
+pub fn history_csv(history: &[CrawlItem]) -> String {
+    csv_rows(
+        &["session_date", "category", "source", "title", "url", "saved"],
+        history.iter().map(|item| {
+            vec![
+                item.session_date.clone(),
+                item.category.clone(),
+                item.source.clone(),
+                item.title.clone(),
+                item.url.clone(),
+                item.is_saved.to_string(),
+            ]
+        }),
+    )
+}
+
+pub fn stats_csv(days: &[DailyStats]) -> String {
+    csv_rows(
+        &[
+            "date",
+            "memes",
+            "jokes",
+            "news",
+            "videos",
+            "gossip",
+            "total_items",
+            "estimated_time_saved_minutes",
+            "providers_used",
+            "consume_calls",
+        ],
+        days.iter().map(|d| {
+            vec![
+                d.date.clone(),
+                d.stats.memes_found.to_string(),
+                d.stats.jokes_found.to_string(),
+                d.stats.news_checked.to_string(),
+                d.stats.videos_found.to_string(),
+                d.stats.gossip_found.to_string(),
+                d.stats.total_items.to_string(),
+                format!("{:.1}", d.stats.estimated_time_saved_minutes),
+                d.providers_used.to_string(),
+                d.consume_calls.to_string(),
+            ]
+        }),
+    )
+}
+
+fn category_heading(category: &str) -> String {
+    match category {
+        "meme" => "Memes".into(),
+        "joke" => "Jokes".into(),
+        "news" => "News".into(),
+        "video" => "Videos".into(),
+        "gossip" => "Gossip".into(),
+        other => other.to_string(),
+    }
+}
+
+/// Link text with the characters that would end it escaped.
+fn md_link(title: &str, url: &str) -> String {
+    let title = title.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("[{}](<{}>)", title, url)
+}
+
+/// Known categories in their usual order, then anything else alphabetically.
+fn categories_of<'a>(items: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut found: Vec<String> = items.map(str::to_string).collect();
+    found.sort();
+    found.dedup();
+    found.sort_by_key(|c| CATEGORIES.iter().position(|k| k == c).unwrap_or(CATEGORIES.len()));
+    found
+}
+
+pub fn markdown_digest(data: &ExportData) -> String {
+    let mut out = String::from("# Cazzmachine export\n\n");
+    let range = match (&data.from_date, &data.to_date) {
+        (Some(from), Some(to)) => format!("{} to {}", from, to),
+        (Some(from), None) => format!("Since {}", from),
+        (None, Some(to)) => format!("Up to {}", to),
+        (None, None) => "All time".to_string(),
+    };
+    out.push_str(&format!("_{}, generated {}_\n\n", range, data.generated_at));
+
+    let active: Vec<&DailyStats> = data
+        .daily_stats
+        .iter()
+        .filter(|d| d.stats.total_items > 0 || d.consume_calls > 0)
+        .collect();
+    out.push_str("## Daily stats\n\n");
+    if active.is_empty() {
+        out.push_str("No activity in this range.\n\n");
+    } else {
+        out.push_str("| Date | Items | Memes | Jokes | News | Videos | Gossip | Time saved (min) | Providers |\n");
+        out.push_str("|---|---:|---:|---:|---:|---:|---:|---:|---:|\n");
+        for d in active {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} | {:.1} | {} |\n",
+                d.date,
+                d.stats.total_items,
+                d.stats.memes_found,
+                d.stats.jokes_found,
+                d.stats.news_checked,
+                d.stats.videos_found,
+                d.stats.gossip_found,
+                d.stats.estimated_time_saved_minutes,
+                d.providers_used
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Saved\n\n");
+    if data.saved.is_empty() {
+        out.push_str("Nothing saved in this range.\n\n");
+    }
+    for category in categories_of(data.saved.iter().map(|s| s.item.category.as_str())) {
+        let entries: Vec<&LibraryItem> = data.saved.iter().filter(|s| s.item.category == category).collect();
+        out.push_str(&format!("### {} ({})\n\n", category_heading(&category), entries.len()));
+        for entry in entries {
+            out.push_str(&format!(
+                "- {} — {}, saved {}\n",
+                md_link(&entry.item.title, &entry.item.url),
+                entry.item.source,
+                entry.saved_at
+            ));
+            if let Some(note) = &entry.note {
+                out.push_str(&format!("  > {}\n", note.lines().collect::<Vec<_>>().join(" ")));
+            }
+            if !entry.tags.is_empty() {
+                let tags: Vec<String> = entry.tags.iter().map(|t| format!("`{}`", t)).collect();
+                out.push_str(&format!("  Tags: {}\n", tags.join(" ")));
+            }
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## History\n\n");
+    if data.history.is_empty() {
+        out.push_str("Nothing consumed in this range.\n\n");
+    }
+    for category in categories_of(data.history.iter().map(|i| i.category.as_str())) {
+        let items: Vec<&CrawlItem> = data.history.iter().filter(|i| i.category == category).collect();
+        out.push_str(&format!("### {} ({})\n\n", category_heading(&category), items.len()));
+        for item in items {
+            out.push_str(&format!(
+                "- {} — {} ({})\n",
+                md_link(&item.title, &item.url),
+                item.source,
+                item.session_date
+            ));
+        }
+        out.push('\n');
+    }
+
+    out
+}
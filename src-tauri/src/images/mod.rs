@@ -0,0 +1,208 @@
+pub mod protocol;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Total size of stored images above which the least recently used ones are
+/// evicted. Thumbnails of saved items are kept regardless.
+pub const CACHE_MAX_BYTES: i64 = 256 * 1024 * 1024;
+
+/// Largest image download that is decoded; anything bigger is dropped.
+pub const MAX_DOWNLOAD_BYTES: usize = 16 * 1024 * 1024;
+
+/// Default for the `thumbnail_max_px` setting, the longest side thumbnails
+/// are scaled down to.
+pub const DEFAULT_THUMBNAIL_MAX_PX: u32 = 640;
+pub const MIN_THUMBNAIL_MAX_PX: u32 = 64;
+pub const MAX_THUMBNAIL_MAX_PX: u32 = 2048;
+
+/// Quality of re-encoded JPEG thumbnails.
+const JPEG_QUALITY: u8 = 82;
+
+static THUMBNAIL_MAX_PX: AtomicU32 = AtomicU32::new(DEFAULT_THUMBNAIL_MAX_PX);
+
+/// The `thumbnail_max_px` setting currently in effect.
+pub fn thumbnail_max_px() -> u32 {
+    THUMBNAIL_MAX_PX.load(Ordering::Relaxed)
+}
+
+pub fn set_thumbnail_max_px(px: u32) {
+    THUMBNAIL_MAX_PX.store(px, Ordering::Relaxed);
+}
+
+/// MIME type of an image going by its leading magic bytes, for the formats
+/// thumbnails are decoded from: PNG, JPEG, GIF and WebP.
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Turns downloaded bytes into a thumbnail no larger than `max_px` on its
+/// longest side. Images already within bounds are kept as they are; larger
+/// ones are scaled down and re-encoded, as PNG if they have transparency and
+/// JPEG otherwise. GIFs are reduced to their first frame. Returns `None` for
+/// oversized downloads and anything that isn't a decodable image.
+///
+/// Decoding is CPU-bound; call this off the async runtime.
+pub fn thumbnail(bytes: &[u8], max_px: u32) -> Option<ImageData> {
+    if bytes.len() > MAX_DOWNLOAD_BYTES {
+        return None;
+    }
+    let mime = sniff_mime(bytes)?;
+    let format = ImageFormat::from_mime_type(mime)?;
+    // Decodes the first frame of animated GIFs and WebPs
+    let image = image::load_from_memory_with_format(bytes, format).ok()?;
+
+    let (width, height) = image.dimensions();
+    let fits = width <= max_px && height <= max_px;
+    if fits && format != ImageFormat::Gif {
+        return Some(ImageData {
+            mime: mime.to_string(),
+            bytes: bytes.to_vec(),
+        });
+    }
+
+    let image = if fits { image } else { image.thumbnail(max_px, max_px) };
+    encode(&image)
+}
+
+fn encode(image: &DynamicImage) -> Option<ImageData> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    // GIF frames always decode with an alpha channel, mostly unused
+    let transparent = image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX);
+    if transparent {
+        image.write_to(&mut bytes, ImageFormat::Png).ok()?;
+        Some(ImageData {
+            mime: "image/png".to_string(),
+            bytes: bytes.into_inner(),
+        })
+    } else {
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY);
+        image.to_rgb8().write_with_encoder(encoder).ok()?;
+        Some(ImageData {
+            mime: "image/jpeg".to_string(),
+            bytes: bytes.into_inner(),
+        })
+    }
+}
+
+/// An image's bytes with their MIME type, as downloaded or as read back from
+/// the `images` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageData {
+    pub mime: String,
+    pub bytes: Vec<u8>,
+}
+
+impl ImageData {
+    /// Content address the image is stored under: the hex SHA-256 of its bytes.
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(&self.bytes))
+    }
+
+    pub fn to_data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime, STANDARD.encode(&self.bytes))
+    }
+
+    /// Parses a `data:<mime>;base64,...` URL, the form thumbnails were stored
+    /// in before the image table.
+    pub fn from_data_url(data_url: &str) -> Option<Self> {
+        let (header, encoded) = data_url.strip_prefix("data:")?.split_once(";base64,")?;
+        Some(Self {
+            mime: header.to_string(),
+            bytes: STANDARD.decode(encoded).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    fn photo(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        }))
+    }
+
+    #[test]
+    fn test_sniff_mime_ignores_names() {
+        assert_eq!(sniff_mime(&encoded(photo(4, 4), ImageFormat::Png)), Some("image/png"));
+        assert_eq!(sniff_mime(&encoded(photo(4, 4), ImageFormat::Jpeg)), Some("image/jpeg"));
+        assert_eq!(sniff_mime(b"GIF89a\x01\x00\x01\x00"), Some("image/gif"));
+        assert_eq!(sniff_mime(b"RIFF\x24\x00\x00\x00WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff_mime(b"<!doctype html><title>404</title>"), None);
+        assert_eq!(sniff_mime(b""), None);
+    }
+
+    #[test]
+    fn test_small_images_are_kept_as_is() {
+        let png = encoded(photo(32, 20), ImageFormat::Png);
+        let thumb = thumbnail(&png, 64).unwrap();
+        assert_eq!(thumb.mime, "image/png");
+        assert_eq!(thumb.bytes, png);
+    }
+
+    #[test]
+    fn test_large_images_are_downscaled() {
+        let png = encoded(photo(1200, 600), ImageFormat::Png);
+        let thumb = thumbnail(&png, 300).unwrap();
+        assert_eq!(thumb.mime, "image/jpeg", "opaque images are re-encoded as JPEG");
+        let decoded = image::load_from_memory(&thumb.bytes).unwrap();
+        assert_eq!(decoded.dimensions(), (300, 150));
+
+        let transparent = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(400, 400, |x, _| {
+            image::Rgba([255, 0, 0, if x < 200 { 0 } else { 255 }])
+        }));
+        let thumb = thumbnail(&encoded(transparent, ImageFormat::Png), 100).unwrap();
+        assert_eq!(thumb.mime, "image/png", "transparency is kept");
+    }
+
+    #[test]
+    fn test_gifs_become_their_first_frame() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame};
+
+        let frame = |shade: u8| {
+            Frame::from_parts(
+                image::RgbaImage::from_pixel(16, 16, image::Rgba([shade, shade, shade, 255])),
+                0,
+                0,
+                Delay::from_numer_denom_ms(100, 1),
+            )
+        };
+        let mut gif = Vec::new();
+        GifEncoder::new(&mut gif).encode_frames([frame(20), frame(230)]).unwrap();
+
+        let thumb = thumbnail(&gif, 64).unwrap();
+        assert_eq!(thumb.mime, "image/jpeg");
+        let first = image::load_from_memory(&thumb.bytes).unwrap().to_luma8();
+        assert!(first.get_pixel(8, 8)[0] < 60, "the first frame is kept");
+    }
+
+    #[test]
+    fn test_non_images_and_oversized_downloads_are_rejected() {
+        assert_eq!(thumbnail(b"<html>not found</html>", 64), None);
+        assert_eq!(thumbnail(b"GIF87a\x01\x00\x01\x00\x00\x00\x00;", 64), None, "truncated");
+        let mut huge = b"\x89PNG\r\n\x1a\n".to_vec();
+        huge.resize(MAX_DOWNLOAD_BYTES + 1, 0);
+        assert_eq!(thumbnail(&huge, 64), None);
+    }
+}
@@ -0,0 +1,167 @@
+use http::{header, Request, Response, StatusCode, Uri};
+
+use super::ImageData;
+use crate::db::Database;
+use crate::net::HttpService;
+
+/// URI scheme the webview loads images from, registered in `lib.rs`.
+pub const SCHEME: &str = "cazz";
+
+/// Responses for URLs naming a hash never change, so the webview may keep
+/// them for good.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+/// Responses for URLs with only a `src`, which may be re-downloaded later.
+const REVALIDATE: &str = "public, max-age=86400";
+
+/// What a `cazz://image/<hash>?src=<url>` request asks for: a stored image
+/// by hash, downloaded from `src` if it isn't stored (any more). Either part
+/// may be missing, but not both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRequest {
+    pub hash: Option<String>,
+    pub src: Option<String>,
+}
+
+impl ImageRequest {
+    /// Parses both the `cazz://image/<hash>` form and the
+    /// `http://cazz.localhost/image/<hash>` form Windows and Android
+    /// webviews use for custom schemes.
+    pub fn parse(uri: &Uri) -> Option<Self> {
+        let path = uri.path();
+        let rest = if uri.host() == Some("image") {
+            path
+        } else {
+            path.strip_prefix("/image")?
+        };
+        let hash = match rest.trim_start_matches('/') {
+            "" => None,
+            hash if hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) => {
+                Some(hash.to_string())
+            }
+            _ => return None,
+        };
+        let src = uri.query().and_then(|query| {
+            ::url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "src")
+                .map(|(_, value)| value.into_owned())
+                .filter(|value| !value.is_empty())
+        });
+        (hash.is_some() || src.is_some()).then_some(Self { hash, src })
+    }
+}
+
+/// Answers a request for an image. Stored images are served straight from
+/// the image store; on a miss the `src` URL is downloaded as a thumbnail,
+/// stored and linked to the items showing it. Supports `If-None-Match`
+/// against the image hash.
+pub async fn handle(db: &Database, client: &HttpService, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some(image_request) = ImageRequest::parse(request.uri()) else {
+        return error(StatusCode::BAD_REQUEST, "expected cazz://image/<sha256>?src=<url>");
+    };
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+
+    if let Some(hash) = &image_request.hash {
+        if if_none_match == Some(etag(hash).as_str()) {
+            return not_modified(hash);
+        }
+        match db.get_image(hash) {
+            Ok(Some(image)) => return image_response(hash, image, IMMUTABLE),
+            Ok(None) => {}
+            Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        }
+    }
+
+    let Some(src) = image_request.src else {
+        return error(StatusCode::NOT_FOUND, "image not stored");
+    };
+    let url = if src.starts_with("//") { format!("https:{}", src) } else { src.clone() };
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return error(StatusCode::BAD_REQUEST, "src must be an http(s) URL");
+    }
+    let Some(image) = crate::crawler::util::download_image(client, &url).await else {
+        return error(StatusCode::BAD_GATEWAY, "src is not a usable image");
+    };
+
+    let stored = db
+        .store_image(&image)
+        .and_then(|hash| db.link_thumbnail(&src, &hash).map(|_| hash));
+    match stored {
+        Ok(hash) => {
+            // Only a URL naming this exact content may be cached for good
+            let cache_control = if image_request.hash.as_deref() == Some(hash.as_str()) {
+                IMMUTABLE
+            } else {
+                REVALIDATE
+            };
+            image_response(&hash, image, cache_control)
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+fn etag(hash: &str) -> String {
+    format!("\"{}\"", hash)
+}
+
+fn image_response(hash: &str, image: ImageData, cache_control: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, image.mime)
+        .header(header::CONTENT_LENGTH, image.bytes.len())
+        .header(header::CACHE_CONTROL, cache_control)
+        .header(header::ETAG, etag(hash))
+        .body(image.bytes)
+        .expect("image response is valid")
+}
+
+fn not_modified(hash: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::CACHE_CONTROL, IMMUTABLE)
+        .header(header::ETAG, etag(hash))
+        .body(Vec::new())
+        .expect("not modified response is valid")
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(message.as_bytes().to_vec())
+        .expect("error response is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageRequest;
+
+    fn parse(uri: &str) -> Option<ImageRequest> {
+        ImageRequest::parse(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_image_requests() {
+        let hash = "ab".repeat(32);
+        let expected = |src: Option<&str>| ImageRequest { hash: Some(hash.clone()), src: src.map(str::to_string) };
+
+        assert_eq!(parse(&format!("cazz://image/{}", hash)), Some(expected(None)));
+        assert_eq!(parse(&format!("http://cazz.localhost/image/{}", hash)), Some(expected(None)));
+        assert_eq!(
+            parse(&format!("cazz://image/{}?src=https%3A%2F%2Fi.redd.it%2Fa.png%3Fs%3D1", hash)),
+            Some(expected(Some("https://i.redd.it/a.png?s=1")))
+        );
+        assert_eq!(
+            parse("cazz://image/?src=%2F%2Fexample.com%2Fa.jpg"),
+            Some(ImageRequest { hash: None, src: Some("//example.com/a.jpg".to_string()) })
+        );
+
+        assert_eq!(parse("cazz://image/"), None, "nothing to serve");
+        assert_eq!(parse("cazz://image/../../etc/passwd"), None);
+        assert_eq!(parse(&format!("cazz://image/{}", hash.to_uppercase())), None);
+        assert_eq!(parse(&format!("cazz://video/{}", hash)), None);
+    }
+}
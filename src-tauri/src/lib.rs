@@ -1,9 +1,14 @@
 mod commands;
+pub mod consumption;
 pub mod crawler;
 pub mod db;
+pub mod export;
+pub mod images;
+pub mod net;
 mod notifications;
+pub mod settings;
 mod summary;
-#[cfg(target_os = "android")] mod shared;
+pub mod shared;
 
 #[cfg(target_os = "android")]
 mod android;
@@ -30,6 +35,21 @@ pub fn run() {
         .plugin(tauri_plugin_http::init());
 
     builder
+        .register_asynchronous_uri_scheme_protocol(images::protocol::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle();
+            let db = app.try_state::<Arc<db::Database>>().map(|state| state.inner().clone());
+            let http = app.try_state::<Arc<net::HttpService>>().map(|state| state.inner().clone());
+            tauri::async_runtime::spawn(async move {
+                let response = match (db, http) {
+                    (Some(db), Some(http)) => images::protocol::handle(&db, &http, &request).await,
+                    _ => tauri::http::Response::builder()
+                        .status(tauri::http::StatusCode::SERVICE_UNAVAILABLE)
+                        .body(Vec::new())
+                        .unwrap(),
+                };
+                responder.respond(response);
+            });
+        })
         .setup(move |app| {
             let app_dir = app
                 .path()
@@ -42,8 +62,15 @@ pub fn run() {
             // Clear diagnostic logs from previous sessions on startup
             let _ = database.clear_diagnostics(0);
 
+            settings::Settings::load(&database)
+                .unwrap_or_default()
+                .apply();
+
             app.manage(database.clone());
 
+            let http = Arc::new(net::HttpService::new().expect("failed to build HTTP client"));
+            app.manage(http.clone());
+
             let notif_db = database.clone();
             let notif_shutdown = shutdown_rx.clone();
             let app_handle = app.handle().clone();
@@ -52,16 +79,12 @@ pub fn run() {
                 engine.run(app_handle).await;
             });
 
-            #[cfg(target_os = "android")]
-            {
-                let bg_db = database.clone();
-                let bg_shutdown = shutdown_rx.clone();
-
-                tauri::async_runtime::spawn(async move {
-                    let service = android::background_service::AndroidBackgroundService::new(bg_db, bg_shutdown);
-                    service.run().await;
-                });
-            }
+            let crawl_db = database.clone();
+            let crawl_shutdown = shutdown_rx.clone();
+            tauri::async_runtime::spawn(async move {
+                let scheduler = shared::crawl_scheduler::CrawlScheduler::new(crawl_db, http, crawl_shutdown);
+                scheduler.run().await;
+            });
 
             Ok(())
         })
@@ -73,9 +96,19 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::get_today_items,
             commands::get_items_by_category,
+            commands::search_items,
             commands::get_today_stats,
+            commands::get_stats_summary,
+            commands::get_stats_series,
             commands::get_daily_summary,
             commands::toggle_save_item,
+            commands::get_saved_items,
+            commands::export_data,
+            commands::export_bundle,
+            commands::import_bundle,
+            commands::set_saved_item_note,
+            commands::set_saved_item_tags,
+            commands::get_item_duplicates,
             commands::mark_item_seen,
             commands::consume_pending_items,
             commands::open_url,
@@ -83,6 +116,8 @@ pub fn run() {
             commands::set_throttle_level,
             commands::get_consumption_threads,
             commands::set_consumption_threads,
+            commands::get_settings,
+            commands::update_settings,
             commands::get_pending_count,
             commands::prune_old_items,
             commands::get_last_active_timestamp,
@@ -92,6 +127,14 @@ pub fn run() {
             commands::get_recent_diagnostics,
             commands::clear_diagnostics,
             commands::trigger_crawl,
+            commands::list_sources,
+            commands::add_source,
+            commands::remove_source,
+            commands::set_source_enabled,
+            commands::list_filter_rules,
+            commands::add_filter_rule,
+            commands::remove_filter_rule,
+            commands::set_filter_rule_enabled,
             commands::log_diagnostic,
             commands::fetch_image,
             #[cfg(target_os = "android")]
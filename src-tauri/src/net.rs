@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::header::{HeaderName, HeaderValue, RETRY_AFTER};
+use rand::Rng;
+use reqwest::{IntoUrl, Method, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// User-Agent sent with every request the app makes.
+pub const USER_AGENT: &str = concat!("cazzmachine/", env!("CARGO_PKG_VERSION"));
+
+/// Knobs for `HttpService`. The defaults suit crawling; tests shorten the
+/// delays.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Whole-request timeout, body included.
+    pub timeout: Duration,
+    /// Retries after the first attempt for 5xx and 429 responses.
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubled for each one after.
+    pub base_delay: Duration,
+    /// Longest wait between attempts. A `Retry-After` asking for more
+    /// gives up and returns the response instead.
+    pub max_delay: Duration,
+    /// Requests to one host that may be in flight at once.
+    pub per_host_limit: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15),
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            per_host_limit: 4,
+        }
+    }
+}
+
+/// A token bucket: up to `burst` requests back to back, refilled at
+/// `per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// A provider's request budget on `host`, which also covers its
+/// subdomains (`reddit.com` covers `www.reddit.com`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostLimit {
+    pub host: String,
+    pub limit: RateLimit,
+}
+
+impl HostLimit {
+    pub fn new(host: &str, limit: RateLimit) -> Self {
+        Self {
+            host: host.to_lowercase(),
+            limit,
+        }
+    }
+}
+
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    /// Tokens already taken by `try_reserve` that the next requests spend
+    /// instead of taking their own.
+    reserved: u32,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            reserved: 0,
+            updated: Instant::now(),
+        }
+    }
+
+    fn per_second(&self) -> f64 {
+        self.limit.per_minute.max(1) as f64 / 60.0
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let earned = now.duration_since(self.updated).as_secs_f64() * self.per_second();
+        self.tokens = (self.tokens + earned).min(self.limit.burst as f64);
+        self.updated = now;
+    }
+
+    /// Takes a token, or says how long until there is one.
+    fn take(&mut self) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_second()))
+        }
+    }
+}
+
+/// The app's HTTP client, managed in Tauri state and shared by the crawler,
+/// the image protocol and `fetch_image`.
+///
+/// Connections are pooled across callers, every request carries `USER_AGENT`,
+/// 5xx and 429 responses are retried with jittered exponential backoff (or
+/// after the server's `Retry-After`), and at most `per_host_limit` requests
+/// go to one host at a time. Hosts given a `RateLimit` with
+/// `set_rate_limit` are also held to its token bucket: requests wait for a
+/// token (or spend one set aside with `try_reserve`), and a 429 empties the
+/// bucket.
+pub struct HttpService {
+    client: reqwest::Client,
+    config: HttpConfig,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl HttpService {
+    pub fn new() -> reqwest::Result<Self> {
+        Self::with_config(HttpConfig::default())
+    }
+
+    pub fn with_config(config: HttpConfig) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(config.timeout)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(config.per_host_limit)
+            .build()?;
+        Ok(Self {
+            client,
+            config,
+            hosts: Mutex::new(HashMap::new()),
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    /// Puts `host` (and its subdomains) on a token bucket, or reshapes its
+    /// bucket, keeping the tokens it has left.
+    pub fn set_rate_limit(&self, host: &str, limit: RateLimit) {
+        let mut buckets = self.buckets.lock().unwrap();
+        match buckets.get_mut(&host.to_lowercase()) {
+            Some(bucket) if bucket.limit == limit => {}
+            Some(bucket) => {
+                bucket.refill();
+                bucket.limit = limit;
+                bucket.tokens = bucket.tokens.min(limit.burst as f64);
+            }
+            None => {
+                buckets.insert(host.to_lowercase(), TokenBucket::new(limit));
+            }
+        }
+    }
+
+    /// Takes a token from the bucket covering `host` for a request about to
+    /// be made, or `None` if the bucket is empty. Checking and taking happen
+    /// under one lock, so concurrent callers can't both see the last token.
+    /// Hosts without a rate limit always have budget.
+    pub fn try_reserve(&self, host: &str) -> Option<Reservation<'_>> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if let Some(bucket) = bucket_for(&mut buckets, host) {
+            bucket.take().ok()?;
+            bucket.reserved += 1;
+        }
+        Some(Reservation {
+            service: self,
+            host: host.to_lowercase(),
+        })
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> HttpRequest<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> HttpRequest<'_> {
+        HttpRequest {
+            service: self,
+            builder: self.client.request(method, url),
+        }
+    }
+
+    /// Waits for a slot on the request's host, then sends it, retrying
+    /// while the server answers 5xx or 429. The last response is returned
+    /// whatever its status; only transport errors are `Err`.
+    async fn execute(&self, mut request: reqwest::Request) -> reqwest::Result<Response> {
+        let host = request.url().host_str().unwrap_or_default().to_lowercase();
+        let _permit = self.host_permit(&host).await;
+        let mut attempt = 0;
+        loop {
+            let retry = if attempt < self.config.max_retries { request.try_clone() } else { None };
+            self.take_token(&host).await;
+            let response = self.client.execute(request).await?;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                if let Some(bucket) = bucket_for(&mut self.buckets.lock().unwrap(), &host) {
+                    bucket.tokens = 0.0;
+                    bucket.updated = Instant::now();
+                }
+            }
+            let (Some(next), Some(delay)) = (retry, self.retry_delay(&response, attempt)) else {
+                return Ok(response);
+            };
+            drop(response);
+            tokio::time::sleep(delay).await;
+            request = next;
+            attempt += 1;
+        }
+    }
+
+    /// The slot is held through any backoff, so a host that is pushing
+    /// back isn't hit by the other callers meanwhile.
+    async fn host_permit(&self, host: &str) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.config.per_host_limit.max(1))))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Spends a reserved token of the bucket covering `host`, if any, or
+    /// waits until the bucket has one and takes it.
+    async fn take_token(&self, host: &str) {
+        loop {
+            let wait = match bucket_for(&mut self.buckets.lock().unwrap(), host) {
+                Some(bucket) if bucket.reserved > 0 => {
+                    bucket.reserved -= 1;
+                    return;
+                }
+                Some(bucket) => match bucket.take() {
+                    Ok(()) => return,
+                    Err(wait) => wait,
+                },
+                None => return,
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// How long to wait before retrying `response`, or `None` if it
+    /// shouldn't be retried.
+    fn retry_delay(&self, response: &Response, attempt: u32) -> Option<Duration> {
+        let status = response.status();
+        if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+            return None;
+        }
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+        match retry_after {
+            Some(delay) => (delay <= self.config.max_delay).then_some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// `base_delay * 2^attempt`, capped at `max_delay`, with the lower half
+    /// jittered away so retrying callers spread out.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .config
+            .base_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.config.max_delay);
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// A token set aside by `HttpService::try_reserve`. Requests to the host
+/// spend reserved tokens first; dropping the reservation gives its token
+/// back if none did.
+pub struct Reservation<'a> {
+    service: &'a HttpService,
+    host: String,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut buckets = self.service.buckets.lock().unwrap();
+        if let Some(bucket) = bucket_for(&mut buckets, &self.host) {
+            if bucket.reserved > 0 {
+                bucket.reserved -= 1;
+                bucket.tokens = (bucket.tokens + 1.0).min(bucket.limit.burst as f64);
+            }
+        }
+    }
+}
+
+/// A request being built against an `HttpService`; `send` goes through the
+/// service's host limit and retries.
+pub struct HttpRequest<'a> {
+    service: &'a HttpService,
+    builder: reqwest::RequestBuilder,
+}
+
+impl HttpRequest<'_> {
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.builder = self.builder.header(key, value);
+        self
+    }
+
+    pub async fn send(self) -> reqwest::Result<Response> {
+        let request = self.builder.build()?;
+        self.service.execute(request).await
+    }
+}
+
+/// The bucket for `host` or the closest parent domain that has one.
+fn bucket_for<'a>(buckets: &'a mut HashMap<String, TokenBucket>, host: &str) -> Option<&'a mut TokenBucket> {
+    let host = host.to_lowercase();
+    let key = std::iter::successors(Some(host.as_str()), |h| h.split_once('.').map(|(_, parent)| parent))
+        .find(|candidate| buckets.contains_key(*candidate))?
+        .to_string();
+    buckets.get_mut(&key)
+}
+
+/// Parses a `Retry-After` value: either delay-seconds or an HTTP date,
+/// which counts from `now` (dates in the past mean "now").
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+
+    #[test]
+    fn test_buckets_cover_subdomains() {
+        let limit = RateLimit { burst: 1, per_minute: 1 };
+        let mut buckets = HashMap::from([("reddit.com".to_string(), TokenBucket::new(limit))]);
+
+        assert!(bucket_for(&mut buckets, "reddit.com").is_some());
+        assert!(bucket_for(&mut buckets, "WWW.Reddit.com").is_some());
+        assert!(bucket_for(&mut buckets, "notreddit.com").is_none());
+        assert!(bucket_for(&mut buckets, "com").is_none());
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let service = HttpService::with_config(HttpConfig {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            ..HttpConfig::default()
+        })
+        .unwrap();
+
+        for _ in 0..20 {
+            let first = service.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = service.backoff(2);
+            assert!(third >= Duration::from_millis(175) && third <= Duration::from_millis(350));
+            assert!(service.backoff(40) <= Duration::from_millis(350));
+        }
+    }
+}
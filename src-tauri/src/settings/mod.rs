@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+
+use crate::commands::{THREAD_COUNT, THROTTLE_LEVEL};
+use crate::consumption::{CostModel, StrategyKind};
+use crate::crawler::limits::RateLimitOverrides;
+use crate::db::Database;
+use crate::images;
+
+const THROTTLE_LEVEL_KEY: &str = "throttle_level";
+const CONSUMPTION_THREADS_KEY: &str = "consumption_threads";
+const CONSUMPTION_STRATEGY_KEY: &str = "consumption_strategy";
+/// Stored as JSON.
+const COST_MODEL_KEY: &str = "cost_model";
+const THUMBNAIL_MAX_PX_KEY: &str = "thumbnail_max_px";
+/// Stored as JSON.
+const RATE_LIMITS_KEY: &str = "rate_limits";
+
+/// User settings persisted in the `app_state` key/value table.
+///
+/// The hot values are mirrored into the `THROTTLE_LEVEL` / `THREAD_COUNT`
+/// atomics, which the crawler and notification engine read on every cycle,
+/// and into the current consumption strategy, cost model, thumbnail size and
+/// rate limit overrides.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    pub throttle_level: u8,
+    pub consumption_threads: u8,
+    #[serde(default)]
+    pub consumption_strategy: StrategyKind,
+    #[serde(default)]
+    pub cost_model: CostModel,
+    /// Longest side, in pixels, downloaded thumbnails are scaled down to.
+    #[serde(default = "default_thumbnail_max_px")]
+    pub thumbnail_max_px: u32,
+    /// Per-provider overrides of the request budgets providers declare.
+    #[serde(default)]
+    pub rate_limits: RateLimitOverrides,
+}
+
+fn default_thumbnail_max_px() -> u32 {
+    images::DEFAULT_THUMBNAIL_MAX_PX
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            throttle_level: 5,
+            consumption_threads: 1,
+            consumption_strategy: StrategyKind::default(),
+            cost_model: CostModel::default(),
+            thumbnail_max_px: images::DEFAULT_THUMBNAIL_MAX_PX,
+            rate_limits: RateLimitOverrides::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The `app_state` keys settings are stored under.
+    pub const KEYS: [&'static str; 6] = [
+        THROTTLE_LEVEL_KEY,
+        CONSUMPTION_THREADS_KEY,
+        CONSUMPTION_STRATEGY_KEY,
+        COST_MODEL_KEY,
+        THUMBNAIL_MAX_PX_KEY,
+        RATE_LIMITS_KEY,
+    ];
+
+    /// Reads settings from the database, falling back to defaults for
+    /// missing or unparseable keys.
+    pub fn load(db: &Database) -> rusqlite::Result<Self> {
+        let mut settings = Self::default();
+        for key in Self::KEYS {
+            if let Some(value) = db.get_app_state(key)? {
+                settings.set(key, &value);
+            }
+        }
+        Ok(settings.clamped())
+    }
+
+    /// Sets the setting stored under `key` from its `app_state` value.
+    /// Returns false, changing nothing, for unknown keys and values that
+    /// don't parse.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            THROTTLE_LEVEL_KEY => value.parse().map(|v| self.throttle_level = v).is_ok(),
+            CONSUMPTION_THREADS_KEY => value.parse().map(|v| self.consumption_threads = v).is_ok(),
+            CONSUMPTION_STRATEGY_KEY => StrategyKind::parse(value)
+                .map(|v| self.consumption_strategy = v)
+                .is_some(),
+            COST_MODEL_KEY => serde_json::from_str(value).map(|v| self.cost_model = v).is_ok(),
+            THUMBNAIL_MAX_PX_KEY => value.parse().map(|v| self.thumbnail_max_px = v).is_ok(),
+            RATE_LIMITS_KEY => serde_json::from_str(value).map(|v| self.rate_limits = v).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// The `app_state` entries these settings are stored as.
+    pub fn entries(&self) -> [(&'static str, String); 6] {
+        let cost_model = serde_json::to_string(&self.cost_model).expect("cost model serializes");
+        let rate_limits = serde_json::to_string(&self.rate_limits).expect("rate limits serialize");
+        [
+            (THROTTLE_LEVEL_KEY, self.throttle_level.to_string()),
+            (CONSUMPTION_THREADS_KEY, self.consumption_threads.to_string()),
+            (CONSUMPTION_STRATEGY_KEY, self.consumption_strategy.as_str().to_string()),
+            (COST_MODEL_KEY, cost_model),
+            (THUMBNAIL_MAX_PX_KEY, self.thumbnail_max_px.to_string()),
+            (RATE_LIMITS_KEY, rate_limits),
+        ]
+    }
+
+    pub fn save(&self, db: &Database) -> rusqlite::Result<()> {
+        for (key, value) in self.entries() {
+            db.set_app_state(key, &value)?;
+        }
+        Ok(())
+    }
+
+    pub fn clamped(self) -> Self {
+        Self {
+            throttle_level: self.throttle_level.clamp(1, 9),
+            consumption_threads: self.consumption_threads.clamp(1, 8),
+            cost_model: self.cost_model.clamped(),
+            thumbnail_max_px: self
+                .thumbnail_max_px
+                .clamp(images::MIN_THUMBNAIL_MAX_PX, images::MAX_THUMBNAIL_MAX_PX),
+            rate_limits: self.rate_limits.clamped(),
+            ..self
+        }
+    }
+
+    /// Snapshot of the settings currently in effect.
+    pub fn current() -> Self {
+        Self {
+            throttle_level: THROTTLE_LEVEL.load(Ordering::Relaxed),
+            consumption_threads: THREAD_COUNT.load(Ordering::Relaxed),
+            consumption_strategy: StrategyKind::current(),
+            cost_model: CostModel::current(),
+            thumbnail_max_px: images::thumbnail_max_px(),
+            rate_limits: RateLimitOverrides::current(),
+        }
+    }
+
+    /// Makes these settings the ones in effect for the running process.
+    pub fn apply(&self) {
+        THROTTLE_LEVEL.store(self.throttle_level, Ordering::Relaxed);
+        THREAD_COUNT.store(self.consumption_threads, Ordering::Relaxed);
+        self.consumption_strategy.apply();
+        self.cost_model.apply();
+        images::set_thumbnail_max_px(self.thumbnail_max_px);
+        self.rate_limits.apply();
+    }
+
+    /// Clamps, writes through to the database and applies.
+    pub fn persist(self, db: &Database) -> rusqlite::Result<Self> {
+        let settings = self.clamped();
+        settings.save(db)?;
+        settings.apply();
+        Ok(settings)
+    }
+}
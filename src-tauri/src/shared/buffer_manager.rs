@@ -2,14 +2,21 @@ use std::sync::Arc;
 use crate::db::Database;
 use crate::shared::consumption_calculator::ConsumptionCalculator;
 use crate::commands::THREAD_COUNT;
+use crate::crawler;
+use crate::net::HttpService;
+
+/// Upper bound on crawl cycles spent refilling the buffer in one go, so a
+/// target the providers cannot meet doesn't keep the network busy.
+const MAX_CYCLES_PER_REPLENISH: usize = 3;
 
 pub struct BufferManager {
     db: Arc<Database>,
+    http: Arc<HttpService>,
 }
 
 impl BufferManager {
-    pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+    pub fn new(db: Arc<Database>, http: Arc<HttpService>) -> Self {
+        Self { db, http }
     }
 
     /// Check if buffer needs replenishment and download if needed
@@ -48,15 +55,26 @@ impl BufferManager {
         }
     }
 
-    async fn download_items(&self, _count: i64) -> Result<(), String> {
-        // This would integrate with the crawler/scheduler to download content
-        self.db.log_diagnostic_event(
-            "buffer_manager",
-            "info",
-            &format!("Would download {} items", _count),
-            None,
-            None
-        ).map_err(|e| e.to_string())?;
+    /// Runs crawl cycles until `count` new items have arrived, a cycle comes
+    /// back empty, or `MAX_CYCLES_PER_REPLENISH` cycles have run.
+    async fn download_items(&self, count: i64) -> Result<(), String> {
+        let mut downloaded = 0i64;
+
+        for cycle in 1..=MAX_CYCLES_PER_REPLENISH {
+            let added = crawler::run_cycle(&self.db, &self.http).await as i64;
+            downloaded += added;
+            self.db.log_diagnostic_event(
+                "buffer_manager",
+                "info",
+                &format!("Crawl cycle {}: {} new items ({} of {} requested)", cycle, added, downloaded, count),
+                None,
+                None
+            ).map_err(|e| e.to_string())?;
+
+            if added == 0 || downloaded >= count {
+                break;
+            }
+        }
         Ok(())
     }
 
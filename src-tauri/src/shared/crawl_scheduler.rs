@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use crate::db::Database;
+use crate::net::HttpService;
+use crate::shared::buffer_manager::BufferManager;
+use crate::shared::get_shared_state;
+
+/// Periodically tops up the pending buffer on every platform.
+///
+/// Each check asks `BufferManager` to compare the pending count against the
+/// target from `ConsumptionCalculator::calculate_buffer_requirements` and to
+/// crawl the difference, so content keeps arriving without the frontend
+/// calling `trigger_crawl`.
+pub struct CrawlScheduler {
+    db: Arc<Database>,
+    buffer: BufferManager,
+    shutdown_rx: watch::Receiver<bool>,
+}
+
+impl CrawlScheduler {
+    pub fn new(db: Arc<Database>, http: Arc<HttpService>, shutdown_rx: watch::Receiver<bool>) -> Self {
+        Self {
+            buffer: BufferManager::new(db.clone(), http),
+            db,
+            shutdown_rx,
+        }
+    }
+
+    fn check_interval(&self) -> Duration {
+        Duration::from_secs(get_shared_state().download_interval_minutes as u64 * 60)
+    }
+
+    async fn check(&self) {
+        // Day rollover: write the stats rollup for any day that just finished
+        match self.db.roll_up_stats() {
+            Ok(days) if days > 0 => {
+                let _ = self.db.log_diagnostic_event("stats_rollup", "info", &format!("Rolled up stats for {} day(s)", days), None, None);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let _ = self.db.log_diagnostic_event("stats_rollup", "warn", &format!("Stats rollup failed: {}", e), None, None);
+            }
+        }
+
+        match self.buffer.replenish_buffer_if_needed().await {
+            Ok(added) if added > 0 => {
+                let _ = self.db.log_diagnostic_event("crawl_scheduler", "info", &format!("Buffer replenished with {} items", added), None, None);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let _ = self.db.log_diagnostic_event("crawl_scheduler_error", "warn", &format!("Buffer replenishment failed: {}", e), None, None);
+            }
+        }
+    }
+
+    pub async fn run(&self) {
+        let _ = self.db.log_diagnostic_event("crawl_scheduler", "info", "CrawlScheduler started", None, None);
+
+        // Initial check on startup (after short delay to let app stabilize)
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        self.check().await;
+
+        loop {
+            let interval = self.check_interval();
+            let mut rx = self.shutdown_rx.clone();
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {
+                    self.check().await;
+                }
+                _ = rx.changed() => {
+                    if *rx.borrow() {
+                        let _ = self.db.log_diagnostic_event("crawl_scheduler", "info", "CrawlScheduler shutting down", None, None);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 pub mod consumption_calculator;
 pub mod buffer_manager;
 pub mod lifecycle_manager;
+pub mod crawl_scheduler;
 
 use std::sync::atomic::{AtomicI64, AtomicBool};
 use std::sync::OnceLock;
@@ -1,12 +1,18 @@
 //! Integration and unit tests for crawler image downloading
 //!
 //! These tests verify that:
-//! 1. The download_image() function correctly downloads and encodes images
-//! 2. Crawlers properly populate thumbnail_data when fetching content
+//! 1. The download_image() function correctly downloads images
+//! 2. Crawlers properly populate thumbnail_data when fetching content, and
+//!    stored items reference their thumbnail by hash
 //! 3. Image format detection and MIME types are correct
 
-use cazzmachine_lib::crawler::provider::ContentProvider;
+use std::time::{Duration, Instant};
+
+use cazzmachine_lib::crawler::error::FetchError;
+use cazzmachine_lib::crawler::executor::CrawlExecutor;
+use cazzmachine_lib::crawler::provider::{ContentProvider, FetchedItem};
 use cazzmachine_lib::db::Database;
+use cazzmachine_lib::net::{HostLimit, HttpConfig, HttpService, RateLimit};
 
 fn create_test_db() -> (Database, tempfile::TempDir) {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -14,23 +20,45 @@ fn create_test_db() -> (Database, tempfile::TempDir) {
     (db, temp_dir)
 }
 
-/// Test 1: Verify download_image() successfully downloads and encodes PNG images
+/// A fetched item without thumbnail, description or metadata.
+fn fetched_item(source: &str, category: &str, title: &str, url: &str) -> FetchedItem {
+    FetchedItem {
+        source: source.into(),
+        category: category.into(),
+        title: title.into(),
+        url: url.into(),
+        thumbnail_url: None,
+        thumbnail_data: None,
+        description: None,
+        metadata: Default::default(),
+    }
+}
+
+/// A small gradient encoded as `format`, `width` x `height` pixels.
+fn encoded_image(format: image::ImageFormat, width: u32, height: u32) -> Vec<u8> {
+    let pixels = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([(x * 7) as u8, (y * 5) as u8, 90]));
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(pixels).write_to(&mut bytes, format).unwrap();
+    bytes.into_inner()
+}
+
+/// An `HttpService` that retries quickly, so retry tests don't sleep for long.
+fn fast_retries(max_retries: u32) -> HttpService {
+    HttpService::with_config(HttpConfig {
+        max_retries,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(200),
+        ..HttpConfig::default()
+    })
+    .unwrap()
+}
+
+/// Test 1: Verify download_image() successfully downloads PNG images
 #[tokio::test]
 async fn test_download_image_png() {
     let mut server = mockito::Server::new_async().await;
     
-    // Create a minimal 1x1 PNG image (67 bytes)
-    let png_bytes: Vec<u8> = vec![
-        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
-        0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52, // IHDR chunk
-        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, // 1x1 dimensions
-        0x08, 0x06, 0x00, 0x00, 0x00, 0x1F, 0x15, 0xC4,
-        0x89, 0x00, 0x00, 0x00, 0x0A, 0x49, 0x44, 0x41,
-        0x54, 0x78, 0x9C, 0x63, 0x00, 0x01, 0x00, 0x00,
-        0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00,
-        0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE,
-        0x42, 0x60, 0x82,
-    ];
+    let png_bytes = encoded_image(image::ImageFormat::Png, 8, 8);
 
     let mock = server
         .mock("GET", "/test.png")
@@ -40,7 +68,7 @@ async fn test_download_image_png() {
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/test.png", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -48,13 +76,9 @@ async fn test_download_image_png() {
     mock.assert_async().await;
     assert!(result.is_some(), "download_image should return Some for successful download");
     
-    let data_uri = result.unwrap();
-    assert!(data_uri.starts_with("data:image/png;base64,"), "Should have correct PNG data URI prefix");
-    
-    let base64_part = data_uri.strip_prefix("data:image/png;base64,").unwrap();
-    let decoded = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_part);
-    assert!(decoded.is_ok(), "Base64 encoding should be valid");
-    assert_eq!(decoded.unwrap(), png_bytes, "Decoded bytes should match original");
+    let image = result.unwrap();
+    assert_eq!(image.mime, "image/png", "Should have correct PNG MIME type");
+    assert_eq!(image.bytes, png_bytes, "Bytes should match original");
 }
 
 /// Test 2: Verify download_image() handles JPEG images correctly
@@ -62,12 +86,7 @@ async fn test_download_image_png() {
 async fn test_download_image_jpeg() {
     let mut server = mockito::Server::new_async().await;
     
-    // Minimal JPEG image bytes
-    let jpeg_bytes: Vec<u8> = vec![
-        0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46,
-        0x49, 0x46, 0x00, 0x01, 0x01, 0x00, 0x00, 0x01,
-        0x00, 0x01, 0x00, 0x00, 0xFF, 0xD9,
-    ];
+    let jpeg_bytes = encoded_image(image::ImageFormat::Jpeg, 8, 8);
 
     let mock = server
         .mock("GET", "/test.jpg")
@@ -76,7 +95,7 @@ async fn test_download_image_jpeg() {
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/test.jpg", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -84,26 +103,24 @@ async fn test_download_image_jpeg() {
     mock.assert_async().await;
     assert!(result.is_some());
     
-    let data_uri = result.unwrap();
-    assert!(data_uri.starts_with("data:image/jpeg;base64,"), "Should detect JPEG MIME type");
+    assert_eq!(result.unwrap().mime, "image/jpeg", "Should detect JPEG MIME type");
 }
 
-/// Test 3: Verify download_image() handles GIF images correctly
+/// Test 3: Verify download_image() turns GIFs into a still image
 #[tokio::test]
 async fn test_download_image_gif() {
     let mut server = mockito::Server::new_async().await;
     
-    // Minimal GIF87a image
-    let gif_bytes = b"GIF87a\x01\x00\x01\x00\x00\x00\x00;";
+    let gif_bytes = encoded_image(image::ImageFormat::Gif, 8, 8);
 
     let mock = server
         .mock("GET", "/test.gif")
         .with_status(200)
-        .with_body(gif_bytes)
+        .with_body(&gif_bytes)
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/test.gif", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -111,8 +128,7 @@ async fn test_download_image_gif() {
     mock.assert_async().await;
     assert!(result.is_some());
     
-    let data_uri = result.unwrap();
-    assert!(data_uri.starts_with("data:image/gif;base64,"), "Should detect GIF MIME type");
+    assert_eq!(result.unwrap().mime, "image/jpeg", "GIFs are reduced to a still first frame");
 }
 
 /// Test 4: Verify download_image() handles WebP images correctly
@@ -120,17 +136,16 @@ async fn test_download_image_gif() {
 async fn test_download_image_webp() {
     let mut server = mockito::Server::new_async().await;
     
-    // Minimal WebP image header
-    let webp_bytes = b"RIFF\x00\x00\x00\x00WEBPVP8 \x00\x00\x00\x00";
+    let webp_bytes = encoded_image(image::ImageFormat::WebP, 8, 8);
 
     let mock = server
         .mock("GET", "/test.webp")
         .with_status(200)
-        .with_body(webp_bytes)
+        .with_body(&webp_bytes)
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/test.webp", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -138,8 +153,7 @@ async fn test_download_image_webp() {
     mock.assert_async().await;
     assert!(result.is_some());
     
-    let data_uri = result.unwrap();
-    assert!(data_uri.starts_with("data:image/webp;base64,"), "Should detect WebP MIME type");
+    assert_eq!(result.unwrap().mime, "image/webp", "Should detect WebP MIME type");
 }
 
 /// Test 5: Verify download_image() returns None for 404 errors
@@ -153,7 +167,7 @@ async fn test_download_image_404() {
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/missing.png", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -170,10 +184,11 @@ async fn test_download_image_server_error() {
     let mock = server
         .mock("GET", "/error.png")
         .with_status(500)
+        .expect(3)
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = fast_retries(2);
     let url = format!("{}/error.png", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -185,7 +200,7 @@ async fn test_download_image_server_error() {
 /// Test 7: Verify download_image() returns None for network errors
 #[tokio::test]
 async fn test_download_image_network_error() {
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = "http://invalid-domain-that-does-not-exist-12345.com/test.png";
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, url).await;
@@ -198,18 +213,18 @@ async fn test_download_image_network_error() {
 async fn test_download_image_headers() {
     let mut server = mockito::Server::new_async().await;
     
-    let png_bytes = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    let png_bytes = encoded_image(image::ImageFormat::Png, 8, 8);
 
     let mock = server
         .mock("GET", "/test.png")
-        .match_header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+        .match_header("User-Agent", cazzmachine_lib::net::USER_AGENT)
         .match_header("Referer", "https://www.reddit.com/")
         .with_status(200)
         .with_body(&png_bytes)
         .create_async()
         .await;
 
-    let client = reqwest::Client::new();
+    let client = HttpService::new().unwrap();
     let url = format!("{}/test.png", server.url());
     
     let result = cazzmachine_lib::crawler::util::download_image(&client, &url).await;
@@ -218,6 +233,73 @@ async fn test_download_image_headers() {
     assert!(result.is_some(), "Should successfully download with correct headers");
 }
 
+/// The MIME type comes from the bytes, not the URL: preview.redd.it serves
+/// JPEGs from `.png` URLs with query strings, and PNGs from URLs without one
+#[tokio::test]
+async fn test_download_image_sniffs_mime_from_content() {
+    let mut server = mockito::Server::new_async().await;
+    let jpeg = server
+        .mock("GET", "/preview.png")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "image/png")
+        .with_body(encoded_image(image::ImageFormat::Jpeg, 8, 8))
+        .create_async()
+        .await;
+    let png = server
+        .mock("GET", "/render")
+        .with_status(200)
+        .with_body(encoded_image(image::ImageFormat::Png, 8, 8))
+        .create_async()
+        .await;
+    let html = server
+        .mock("GET", "/removed.jpg")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<html>This image has been removed</html>")
+        .create_async()
+        .await;
+
+    let client = &HttpService::new().unwrap();
+    let download = |path: &str| {
+        let url = format!("{}{}", server.url(), path);
+        async move { cazzmachine_lib::crawler::util::download_image(client, &url).await }
+    };
+
+    let labeled_png = download("/preview.png?width=640&format=pjpg&auto=webp&s=abc").await.unwrap();
+    assert_eq!(labeled_png.mime, "image/jpeg");
+    assert_eq!(download("/render").await.unwrap().mime, "image/png");
+    assert!(download("/removed.jpg").await.is_none(), "pages that aren't images are dropped");
+
+    jpeg.assert_async().await;
+    png.assert_async().await;
+    html.assert_async().await;
+}
+
+/// Originals larger than the thumbnail resolution are scaled down
+#[tokio::test]
+async fn test_download_image_downscales_large_images() {
+    use image::GenericImageView;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/huge.png")
+        .with_status(200)
+        .with_body(encoded_image(image::ImageFormat::Png, 2000, 1000))
+        .create_async()
+        .await;
+
+    let client = HttpService::new().unwrap();
+    let url = format!("{}/huge.png", server.url());
+    let image = cazzmachine_lib::crawler::util::download_image(&client, &url).await.unwrap();
+
+    mock.assert_async().await;
+    let max_px = cazzmachine_lib::images::thumbnail_max_px();
+    let (width, height) = image::load_from_memory(&image.bytes).unwrap().dimensions();
+    assert_eq!((width, height), (max_px, max_px / 2));
+    assert_eq!(image.mime, "image/jpeg");
+}
+
 /// Test 9: Integration test - Verify meme crawler populates thumbnail_data
 #[tokio::test]
 async fn test_meme_crawler_downloads_images() {
@@ -263,11 +345,18 @@ async fn test_meme_crawler_downloads_images() {
 
 }
 
-/// Test 10: Integration test - Verify items with images have thumbnail_data in database
+/// Test 10: Integration test - Verify items with images reference the stored image
 #[tokio::test]
 async fn test_crawler_image_data_in_database() {
     let (db, _temp_dir) = create_test_db();
     
+    let image = cazzmachine_lib::images::ImageData {
+        mime: "image/png".to_string(),
+        bytes: vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A],
+    };
+    let hash = db.store_image(&image).unwrap();
+    assert_eq!(hash, image.hash());
+
     let now = chrono::Local::now();
     let test_item = cazzmachine_lib::db::models::CrawlItem {
         id: format!("test-image-{}", uuid::Uuid::new_v4()),
@@ -276,13 +365,14 @@ async fn test_crawler_image_data_in_database() {
         title: "Test Meme with Image".to_string(),
         url: "https://example.com/test".to_string(),
         thumbnail_url: Some("https://example.com/image.png".to_string()),
-        thumbnail_data: Some("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==".to_string()),
+        thumbnail_hash: Some(hash.clone()),
         description: None,
         fetched_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
         is_seen: false,
         is_saved: false,
         is_consumed: false,
         session_date: now.format("%Y-%m-%d").to_string(),
+        metadata: Default::default(),
     };
 
     db.insert_item(&test_item).unwrap();
@@ -294,11 +384,11 @@ async fn test_crawler_image_data_in_database() {
     assert!(found_item.is_some(), "Should find the inserted item");
     
     let item = found_item.unwrap();
-    assert!(item.thumbnail_data.is_some(), "thumbnail_data should be preserved in database");
-    assert_eq!(item.thumbnail_data.as_ref().unwrap(), &test_item.thumbnail_data.unwrap(), "thumbnail_data should match what was inserted");
+    assert_eq!(item.thumbnail_hash.as_deref(), Some(hash.as_str()), "thumbnail_hash should be preserved in database");
+    assert_eq!(db.get_image(&hash).unwrap(), Some(image), "the image should be stored once under its hash");
 }
 
-/// Test 11: Verify items without images have None for thumbnail_data
+/// Test 11: Verify items without images have None for thumbnail_hash
 #[tokio::test]
 async fn test_items_without_images() {
     let (db, _temp_dir) = create_test_db();
@@ -311,13 +401,14 @@ async fn test_items_without_images() {
         title: "Why did the chicken cross the road?".to_string(),
         url: "https://icanhazdadjoke.com/j/test123".to_string(),
         thumbnail_url: None,
-        thumbnail_data: None,
+        thumbnail_hash: None,
         description: Some("Because it wanted to!".to_string()),
         fetched_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
         is_seen: false,
         is_saved: false,
         is_consumed: false,
         session_date: now.format("%Y-%m-%d").to_string(),
+        metadata: Default::default(),
     };
 
     db.insert_item(&test_item).unwrap();
@@ -329,7 +420,7 @@ async fn test_items_without_images() {
     assert!(found_item.is_some(), "Should find the inserted item");
     
     let item = found_item.unwrap();
-    assert!(item.thumbnail_data.is_none(), "thumbnail_data should be None for items without images");
+    assert!(item.thumbnail_hash.is_none(), "thumbnail_hash should be None for items without images");
     assert!(item.thumbnail_url.is_none(), "thumbnail_url should be None for items without images");
 }
 
@@ -338,7 +429,7 @@ async fn test_items_without_images() {
 async fn test_concurrent_image_downloads() {
     let mut server = mockito::Server::new_async().await;
     
-    let png_bytes = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    let png_bytes = encoded_image(image::ImageFormat::Png, 8, 8);
 
     let mut mocks = vec![];
     for i in 0..10 {
@@ -351,7 +442,7 @@ async fn test_concurrent_image_downloads() {
         mocks.push(mock);
     }
 
-    let client = reqwest::Client::new();
+    let client = std::sync::Arc::new(HttpService::new().unwrap());
 
     let mut handles = vec![];
     for i in 0..10 {
@@ -380,13 +471,10 @@ async fn test_concurrent_image_downloads() {
 
 #[tokio::test]
 async fn test_jokeapi_provider_fetches_jokes() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::jokeapi::JokeApiProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.expect("fetch should succeed");
     
     assert!(!items.is_empty(), "JokeAPI should return at least some jokes");
     
@@ -402,13 +490,10 @@ async fn test_jokeapi_provider_fetches_jokes() {
 
 #[tokio::test]
 async fn test_uselessfacts_provider_fetches_facts() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::uselessfacts::UselessFactsProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.expect("fetch should succeed");
     
     assert!(!items.is_empty());
     
@@ -423,13 +508,10 @@ async fn test_uselessfacts_provider_fetches_facts() {
 
 #[tokio::test]
 async fn test_chucknorris_provider_fetches_jokes() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::chucknorris::ChuckNorrisProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.expect("fetch should succeed");
     
     assert!(!items.is_empty());
     
@@ -445,13 +527,10 @@ async fn test_chucknorris_provider_fetches_jokes() {
 
 #[tokio::test]
 async fn test_hackernews_provider_fetches_news() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::hackernews::HackerNewsProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.expect("fetch should succeed");
     
     assert!(!items.is_empty());
     
@@ -466,13 +545,10 @@ async fn test_hackernews_provider_fetches_news() {
 
 #[tokio::test]
 async fn test_bbcnews_provider_fetches_news() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::bbcnews::BbcNewsProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.expect("fetch should succeed");
     
     assert!(!items.is_empty());
     
@@ -512,13 +588,10 @@ async fn test_all_new_providers_have_valid_names() {
 
 #[tokio::test]
 async fn test_reddit_video_provider_fetches_videos() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::video::RedditVideoProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.unwrap_or_default();
     
     assert_eq!(provider.name(), "reddit-videos");
     assert_eq!(provider.category(), "video");
@@ -535,13 +608,10 @@ async fn test_reddit_video_provider_fetches_videos() {
 
 #[tokio::test]
 async fn test_reddit_video_provider_filters_nsfw() {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .unwrap();
+    let client = HttpService::with_config(HttpConfig { timeout: Duration::from_secs(30), ..HttpConfig::default() }).unwrap();
     
     let provider = cazzmachine_lib::crawler::video::RedditVideoProvider;
-    let items = provider.fetch(&client).await;
+    let items = provider.fetch(&client).await.unwrap_or_default();
     
     for item in &items {
         let title_lower = item.title.to_lowercase();
@@ -552,3 +622,841 @@ async fn test_reddit_video_provider_filters_nsfw() {
     
     println!("Verified {} items are safe for work", items.len());
 }
+
+#[test]
+fn test_registry_lookup_by_name_and_category() {
+    let registry = cazzmachine_lib::crawler::registry::ProviderRegistry::builtin();
+
+    let mut names: Vec<String> = registry.descriptors().into_iter().map(|(n, _)| n).collect();
+    let total = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), total, "Provider names must be unique");
+
+    for name in ["hackernews", "bbc-news", "jokeapi", "reddit-memes", "google-news"] {
+        assert!(registry.get(name).is_some(), "{} should be registered", name);
+    }
+    assert!(registry.get("does-not-exist").is_none());
+
+    let news = registry.by_category("news");
+    assert!(!news.is_empty());
+    assert!(news.iter().all(|p| p.category() == "news"));
+
+    let rotation = registry.rotation(registry.len() + 5);
+    assert_eq!(rotation.len(), registry.len(), "Rotation never repeats a provider");
+}
+
+// ============================================================================
+// Fetch error classification
+// ============================================================================
+
+#[tokio::test]
+async fn test_fetch_json_rate_limited_with_retry_after() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server
+        .mock("GET", "/listing.json")
+        .with_status(429)
+        .with_header("Retry-After", "120")
+        .create_async()
+        .await;
+
+    let client = HttpService::new().unwrap();
+    let url = format!("{}/listing.json", server.url());
+    let result: Result<serde_json::Value, FetchError> =
+        cazzmachine_lib::crawler::util::fetch_json(&client, &url).await;
+
+    let err = result.unwrap_err();
+    assert_eq!(err, FetchError::RateLimited { retry_after_secs: Some(120) });
+    assert_eq!(err.status_code(), Some(429));
+}
+
+#[tokio::test]
+async fn test_fetch_json_http_status_and_decode_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let _gone = server
+        .mock("GET", "/gone.json")
+        .with_status(503)
+        .create_async()
+        .await;
+    let _garbage = server
+        .mock("GET", "/garbage.json")
+        .with_status(200)
+        .with_body("<html>not json</html>")
+        .create_async()
+        .await;
+
+    let client = HttpService::new().unwrap();
+
+    let gone: Result<serde_json::Value, FetchError> =
+        cazzmachine_lib::crawler::util::fetch_json(&client, &format!("{}/gone.json", server.url())).await;
+    assert_eq!(gone.unwrap_err(), FetchError::HttpStatus { status: 503 });
+
+    let garbage: Result<serde_json::Value, FetchError> =
+        cazzmachine_lib::crawler::util::fetch_json(&client, &format!("{}/garbage.json", server.url())).await;
+    assert_eq!(garbage.unwrap_err().kind(), "decode");
+}
+
+#[tokio::test]
+async fn test_fetch_json_network_error() {
+    let client = HttpService::new().unwrap();
+    let result: Result<serde_json::Value, FetchError> = cazzmachine_lib::crawler::util::fetch_json(
+        &client,
+        "http://invalid-domain-that-does-not-exist-12345.com/listing.json",
+    )
+    .await;
+
+    assert_eq!(result.unwrap_err().kind(), "network");
+}
+
+#[tokio::test]
+async fn test_download_thumbnails_fills_items_in_order() {
+    let mut server = mockito::Server::new_async().await;
+    let png_bytes = encoded_image(image::ImageFormat::Png, 8, 8);
+    let ok = server
+        .mock("GET", "/ok.png")
+        .with_status(200)
+        .with_body(&png_bytes)
+        .create_async()
+        .await;
+    let missing = server
+        .mock("GET", "/missing.png")
+        .with_status(404)
+        .create_async()
+        .await;
+
+    let item = |thumbnail_url: Option<String>| FetchedItem {
+        thumbnail_url,
+        ..fetched_item("test", "meme", "t", "https://example.com")
+    };
+    let mut items = vec![
+        item(Some(format!("{}/ok.png", server.url()))),
+        item(None),
+        item(Some(format!("{}/missing.png", server.url()))),
+    ];
+
+    let client = HttpService::new().unwrap();
+    cazzmachine_lib::crawler::util::download_thumbnails(&client, &mut items).await;
+
+    assert_eq!(items[0].thumbnail_data.as_ref().unwrap().mime, "image/png");
+    assert!(items[1].thumbnail_data.is_none());
+    assert!(items[2].thumbnail_data.is_none());
+    ok.assert_async().await;
+    missing.assert_async().await;
+}
+
+struct SleepyProvider {
+    name: &'static str,
+    delay: Duration,
+}
+
+#[async_trait::async_trait]
+impl ContentProvider for SleepyProvider {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn category(&self) -> &str {
+        "joke"
+    }
+
+    async fn fetch(&self, _client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        tokio::time::sleep(self.delay).await;
+        Ok(vec![fetched_item(
+            self.name,
+            "joke",
+            &format!("{} joke", self.name),
+            &format!("https://example.com/{}", self.name),
+        )])
+    }
+}
+
+fn run_outcome(db: &Database, provider: &str) -> String {
+    let conn = db.conn.lock().unwrap();
+    conn.query_row(
+        "SELECT outcome FROM provider_runs WHERE provider = ?1",
+        [provider],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_executor_runs_providers_concurrently_with_timeout() {
+    let (db, _temp_dir) = create_test_db();
+    let client = HttpService::new().unwrap();
+    let fast_a = SleepyProvider { name: "fast_a", delay: Duration::from_millis(200) };
+    let fast_b = SleepyProvider { name: "fast_b", delay: Duration::from_millis(200) };
+    let stuck = SleepyProvider { name: "stuck", delay: Duration::from_secs(30) };
+
+    let executor = CrawlExecutor::new(3, Duration::from_millis(500));
+    let started = Instant::now();
+    let added = executor
+        .run(&db, &client, vec![&fast_a as &dyn ContentProvider, &fast_b, &stuck])
+        .await;
+    let elapsed = started.elapsed();
+
+    assert_eq!(added, 2);
+    assert!(elapsed < Duration::from_secs(2), "stuck provider stalled the cycle: {:?}", elapsed);
+    assert_eq!(run_outcome(&db, "fast_a"), "success");
+    assert_eq!(run_outcome(&db, "fast_b"), "success");
+    assert_eq!(run_outcome(&db, "stuck"), "timeout");
+}
+
+#[tokio::test]
+async fn test_executor_respects_concurrency_limit() {
+    let (db, _temp_dir) = create_test_db();
+    let client = HttpService::new().unwrap();
+    let providers: Vec<SleepyProvider> = ["a", "b", "c", "d"]
+        .into_iter()
+        .map(|name| SleepyProvider { name, delay: Duration::from_millis(200) })
+        .collect();
+
+    let executor = CrawlExecutor::new(2, Duration::from_secs(5));
+    let started = Instant::now();
+    let added = executor
+        .run(&db, &client, providers.iter().map(|p| p as &dyn ContentProvider).collect())
+        .await;
+
+    assert_eq!(added, 4);
+    // Four 200ms providers two at a time take two rounds
+    assert!(started.elapsed() >= Duration::from_millis(400));
+}
+
+const RSS_FIXTURE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/">
+  <channel>
+    <title>Example News</title>
+    <link>https://news.example.com/</link>
+    <item>
+      <title><![CDATA[Cats &amp; dogs agree on truce]]></title>
+      <link>https://news.example.com/truce</link>
+      <description>&lt;p&gt;Historic &lt;b&gt;moment&lt;/b&gt;&lt;/p&gt;</description>
+      <pubDate>Tue, 10 Jun 2025 04:00:00 GMT</pubDate>
+      <dc:creator>Jane Reporter</dc:creator>
+      <media:thumbnail width="240" height="135" url="https://img.example.com/truce.jpg"/>
+    </item>
+    <item>
+      <title>Podcast episode</title>
+      <link>https://news.example.com/podcast</link>
+      <enclosure url="https://cdn.example.com/ep.mp3" type="audio/mpeg" length="1"/>
+      <enclosure url="https://cdn.example.com/cover.png" type="image/png" length="1"/>
+    </item>
+    <item>
+      <description>No title or link, skipped</description>
+    </item>
+  </channel>
+</rss>"#;
+
+const ATOM_FIXTURE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Example Blog</title>
+  <link href="https://blog.example.com/"/>
+  <entry>
+    <title>Hello Atom</title>
+    <link rel="self" href="https://blog.example.com/hello.atom"/>
+    <link rel="alternate" href="https://blog.example.com/hello"/>
+    <updated>2025-06-10T08:30:00Z</updated>
+    <author><name>Ada</name><email>ada@example.com</email></author>
+    <content type="html">&lt;p&gt;Full body&lt;/p&gt;</content>
+  </entry>
+</feed>"#;
+
+#[test]
+fn test_parse_rss_feed() {
+    use cazzmachine_lib::crawler::rss::parse_feed;
+
+    let entries = parse_feed(RSS_FIXTURE).unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0].title, "Cats &amp; dogs agree on truce", "CDATA is taken verbatim");
+    assert_eq!(entries[0].link, "https://news.example.com/truce");
+    assert_eq!(entries[0].description.as_deref(), Some("<p>Historic <b>moment</b></p>"));
+    assert_eq!(entries[0].image_url.as_deref(), Some("https://img.example.com/truce.jpg"));
+    assert_eq!(entries[0].published_at.as_deref(), Some("2025-06-10T04:00:00+00:00"));
+    assert_eq!(entries[0].author.as_deref(), Some("Jane Reporter"));
+
+    assert_eq!(entries[1].image_url.as_deref(), Some("https://cdn.example.com/cover.png"));
+    assert_eq!(entries[1].description, None);
+    assert_eq!(entries[1].published_at, None);
+}
+
+#[test]
+fn test_parse_atom_feed() {
+    use cazzmachine_lib::crawler::rss::parse_feed;
+
+    let entries = parse_feed(ATOM_FIXTURE).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].title, "Hello Atom");
+    assert_eq!(entries[0].link, "https://blog.example.com/hello");
+    assert_eq!(entries[0].description.as_deref(), Some("<p>Full body</p>"));
+    assert_eq!(entries[0].published_at.as_deref(), Some("2025-06-10T08:30:00+00:00"));
+    assert_eq!(entries[0].author.as_deref(), Some("Ada"), "only the name of an Atom author");
+}
+
+#[test]
+fn test_parse_feed_rejects_non_feeds() {
+    use cazzmachine_lib::crawler::rss::parse_feed;
+
+    assert_eq!(parse_feed("<html><body>hi</body></html>").unwrap_err().kind(), "decode");
+    assert_eq!(parse_feed("<rss><channel><item></rss>").unwrap_err().kind(), "decode");
+}
+
+#[tokio::test]
+async fn test_rss_provider_fetches_feed_directly() {
+    use cazzmachine_lib::crawler::rss::RssProvider;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/feed.xml")
+        .with_status(200)
+        .with_header("content-type", "application/rss+xml")
+        .with_body(RSS_FIXTURE)
+        .create_async()
+        .await;
+
+    let provider = RssProvider::new(
+        "example-news",
+        "news",
+        "Example News",
+        &format!("{}/feed.xml", server.url()),
+    );
+    let items = provider.fetch(&HttpService::new().unwrap()).await.unwrap();
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].source, "Example News");
+    assert_eq!(items[0].category, "news");
+    assert_eq!(items[0].description.as_deref(), Some("Historic moment"));
+    assert_eq!(items[0].thumbnail_url.as_deref(), Some("https://img.example.com/truce.jpg"));
+    assert_eq!(items[0].metadata.author.as_deref(), Some("Jane Reporter"));
+    assert_eq!(items[0].metadata.domain.as_deref(), Some("news.example.com"));
+    let published = chrono::DateTime::parse_from_rfc3339("2025-06-10T04:00:00+00:00")
+        .unwrap()
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    assert_eq!(items[0].metadata.published_at, Some(published));
+    assert_eq!(items[0].metadata.score, None);
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_reddit_post_metadata() {
+    use cazzmachine_lib::crawler::reddit::RedditPostMeta;
+
+    let post = serde_json::json!({
+        "title": "A video",
+        "score": 1234,
+        "num_comments": 56,
+        "author": "someone",
+        "created_utc": 1749528000.0,
+        "domain": "v.redd.it",
+        "media": { "reddit_video": { "duration": 42 } }
+    });
+    let metadata = serde_json::from_value::<RedditPostMeta>(post).unwrap().into_metadata();
+    assert_eq!((metadata.score, metadata.comments), (Some(1234), Some(56)));
+    assert_eq!(metadata.author.as_deref(), Some("someone"));
+    assert_eq!(metadata.domain.as_deref(), Some("v.redd.it"));
+    assert_eq!(metadata.media_duration_secs, Some(42.0));
+    assert!(metadata.published_at.is_some());
+
+    // Deleted authors and null media are common in listings
+    let post = serde_json::json!({ "author": "[deleted]", "media": null });
+    let metadata = serde_json::from_value::<RedditPostMeta>(post).unwrap().into_metadata();
+    assert_eq!(metadata, Default::default());
+}
+
+#[test]
+fn test_source_normalization() {
+    use cazzmachine_lib::crawler::sources::normalize;
+
+    assert_eq!(normalize("subreddit", " r/ProgrammerHumor/ ", "meme").unwrap(), "ProgrammerHumor");
+    assert_eq!(normalize("subreddit", "/r/aww", "meme").unwrap(), "aww");
+    assert!(normalize("subreddit", "no spaces", "meme").is_err());
+    assert_eq!(
+        normalize("feed", "https://example.com/feed.xml", "news").unwrap(),
+        "https://example.com/feed.xml"
+    );
+    assert!(normalize("feed", "ftp://example.com/feed.xml", "news").is_err());
+    assert!(normalize("json_joke", "https://example.com/joke", "cats").is_err());
+    assert!(normalize("mailing_list", "https://example.com", "news").is_err());
+}
+
+#[tokio::test]
+async fn test_json_joke_provider_understands_common_shapes() {
+    use cazzmachine_lib::crawler::jsonjoke::JsonJokeProvider;
+
+    let mut server = mockito::Server::new_async().await;
+    let single = server
+        .mock("GET", "/single")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"setup": "Why?", "punchline": "Because."}"#)
+        .create_async()
+        .await;
+    let wrapped = server
+        .mock("GET", "/wrapped")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"jokes": [{"joke": "One"}, {"value": "Two"}, {"nothing": 3}]}"#)
+        .create_async()
+        .await;
+
+    let client = HttpService::new().unwrap();
+    let single_url = format!("{}/single", server.url());
+    let items = JsonJokeProvider::new(&single_url, "joke", "example")
+        .fetch(&client)
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].title, "Why? Because.");
+    assert!(items[0].url.starts_with(&format!("{}#", single_url)));
+
+    let items = JsonJokeProvider::new(&format!("{}/wrapped", server.url()), "joke", "example")
+        .fetch(&client)
+        .await
+        .unwrap();
+    let titles: Vec<&str> = items.iter().map(|i| i.title.as_str()).collect();
+    assert_eq!(titles, ["One", "Two"]);
+    assert_ne!(items[0].url, items[1].url);
+
+    single.assert_async().await;
+    wrapped.assert_async().await;
+}
+
+fn muted_candidate(source: &str, title: &str, url: &str) -> FetchedItem {
+    FetchedItem {
+        description: Some("Spoilers for the finale inside".into()),
+        ..fetched_item(source, "meme", title, url)
+    }
+}
+
+#[test]
+fn test_mute_list_rule_kinds() {
+    use cazzmachine_lib::crawler::filters::{normalize, MuteList};
+    use cazzmachine_lib::db::models::FilterRule;
+
+    let rule = |kind: &str, pattern: &str| FilterRule {
+        id: format!("{}-rule", kind),
+        kind: kind.into(),
+        pattern: normalize(kind, pattern).unwrap(),
+        enabled: true,
+        hit_count: 0,
+        created_at: String::new(),
+    };
+    let matched = |list: &MuteList, item: &FetchedItem| list.matching_rule(item).map(|r| r.id.clone());
+
+    let keyword = MuteList::new(vec![rule("keyword", "SPOILERS")]);
+    let item = muted_candidate("r/tv", "Finale thread", "https://reddit.com/r/tv/comments/1/");
+    assert_eq!(matched(&keyword, &item).as_deref(), Some("keyword-rule"), "keywords match the description too");
+
+    let regex = MuteList::new(vec![rule("regex", r"\bcrypto\w*")]);
+    assert!(matched(&regex, &muted_candidate("r/x", "Cryptocurrency crash", "https://a.com/1")).is_some());
+    assert!(matched(&regex, &muted_candidate("r/x", "Nothing here", "https://a.com/1")).is_none());
+
+    let domain = MuteList::new(vec![rule("domain", "https://www.example.com/")]);
+    assert!(matched(&domain, &muted_candidate("news", "t", "https://example.com/a")).is_some());
+    assert!(matched(&domain, &muted_candidate("news", "t", "https://news.example.com/a")).is_some());
+    assert!(matched(&domain, &muted_candidate("news", "t", "https://notexample.com/a")).is_none());
+
+    let subreddit = MuteList::new(vec![rule("subreddit", "r/Politics")]);
+    assert!(matched(&subreddit, &muted_candidate("r/politics", "t", "https://reddit.com/r/politics/comments/1/")).is_some());
+    assert!(matched(&subreddit, &muted_candidate("r/memes", "t", "https://reddit.com/r/memes/comments/1/")).is_none());
+
+    let mut disabled = rule("keyword", "spoilers");
+    disabled.enabled = false;
+    assert!(MuteList::new(vec![disabled]).is_empty());
+
+    assert!(normalize("regex", "(unclosed").is_err());
+    assert!(normalize("keyword", "   ").is_err());
+    assert!(normalize("colour", "red").is_err());
+}
+
+struct FixedProvider {
+    items: Vec<FetchedItem>,
+}
+
+#[async_trait::async_trait]
+impl ContentProvider for FixedProvider {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    fn category(&self) -> &str {
+        "meme"
+    }
+
+    async fn fetch(&self, _client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        Ok(self.items.clone())
+    }
+}
+
+#[tokio::test]
+async fn test_crawl_drops_muted_items_and_counts_hits() {
+    let (db, _temp_dir) = create_test_db();
+    let rule = db.add_filter_rule("keyword", "spoilers").unwrap();
+
+    let mut kept = muted_candidate("r/aww", "Puppy", "https://reddit.com/r/aww/comments/2/");
+    kept.description = None;
+    let provider = FixedProvider {
+        items: vec![
+            muted_candidate("r/tv", "Finale thread", "https://reddit.com/r/tv/comments/1/"),
+            kept,
+        ],
+    };
+
+    let added = cazzmachine_lib::crawler::crawl_provider(
+        &db,
+        &HttpService::new().unwrap(),
+        &provider,
+        Duration::from_secs(5),
+    )
+    .await;
+
+    assert_eq!(added, 1);
+    assert_eq!(db.get_pending_count().unwrap(), 1);
+    assert_eq!(db.list_filter_rules().unwrap()[0].hit_count, 1);
+    let filtered = db
+        .get_recent_diagnostics(50)
+        .unwrap()
+        .into_iter()
+        .filter(|d| d.event_type == "item_filtered")
+        .count();
+    assert_eq!(filtered, 1);
+    assert!(db.remove_filter_rule(&rule.id).unwrap());
+}
+
+/// The image scheme serves stored images by hash and fills the store from
+/// `src` on a miss
+#[tokio::test]
+async fn test_image_protocol_serves_and_fills_the_store() {
+    use cazzmachine_lib::images::protocol;
+
+    let (db, _temp_dir) = create_test_db();
+    let mut server = mockito::Server::new_async().await;
+    let png = encoded_image(image::ImageFormat::Png, 8, 8);
+    let remote = server
+        .mock("GET", "/thumb.png")
+        .with_status(200)
+        .with_body(&png)
+        .expect(2)
+        .create_async()
+        .await;
+    let thumbnail_url = format!("{}/thumb.png", server.url());
+    let item = FetchedItem {
+        thumbnail_url: Some(thumbnail_url.clone()),
+        ..fetched_item("test", "meme", "Evicted thumbnail", "https://example.com/evicted")
+    };
+    let (item, _) = item.into_crawl_item();
+    db.insert_item(&item).unwrap();
+
+    let client = HttpService::new().unwrap();
+    let get = |uri: String, etag: Option<&str>| {
+        let mut request = http::Request::builder().uri(uri);
+        if let Some(etag) = etag {
+            request = request.header(http::header::IF_NONE_MATCH, etag);
+        }
+        request.body(Vec::new()).unwrap()
+    };
+
+    let encoded_src = url::form_urlencoded::byte_serialize(thumbnail_url.as_bytes()).collect::<String>();
+    let response = protocol::handle(&db, &client, &get(format!("cazz://image/?src={}", encoded_src), None)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    assert_eq!(response.body(), &png);
+    assert!(!response.headers()["cache-control"].to_str().unwrap().contains("immutable"));
+    let hash = cazzmachine_lib::images::ImageData { mime: "image/png".into(), bytes: png.clone() }.hash();
+    let stored_hash: Option<String> = db
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT thumbnail_hash FROM crawl_items WHERE id = ?1", [&item.id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(stored_hash.as_deref(), Some(hash.as_str()), "the item is linked to the fetched image");
+
+    // Later loads are served from the store, and are cacheable for good
+    let uri = format!("http://cazz.localhost/image/{}?src={}", hash, encoded_src);
+    let response = protocol::handle(&db, &client, &get(uri.clone(), None)).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["cache-control"].to_str().unwrap().contains("immutable"));
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(protocol::handle(&db, &client, &get(uri, Some(&etag))).await.status(), 304);
+
+    // A stale hash refetches `src`, but the URL no longer names what it serves
+    let stale = format!("cazz://image/{}?src={}", "f".repeat(64), encoded_src);
+    let response = protocol::handle(&db, &client, &get(stale, None)).await;
+    assert_eq!(response.status(), 200);
+    assert!(!response.headers()["cache-control"].to_str().unwrap().contains("immutable"));
+    remote.assert_async().await;
+
+    let missing = format!("cazz://image/{}", "0".repeat(64));
+    assert_eq!(protocol::handle(&db, &client, &get(missing, None)).await.status(), 404);
+    assert_eq!(protocol::handle(&db, &client, &get("cazz://image/nope".into(), None)).await.status(), 400);
+}
+
+// ============================================================================
+// HttpService
+// ============================================================================
+
+#[tokio::test]
+async fn test_http_service_retries_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let failing = server.mock("GET", "/flaky").with_status(503).expect(2).create_async().await;
+    let ok = server.mock("GET", "/flaky").with_status(200).with_body("ok").expect(1).create_async().await;
+    let not_found = server.mock("GET", "/missing").with_status(404).expect(1).create_async().await;
+
+    let client = fast_retries(3);
+    let response = client.get(format!("{}/flaky", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "ok");
+
+    let response = client.get(format!("{}/missing", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 404, "client errors are not retried");
+
+    failing.assert_async().await;
+    ok.assert_async().await;
+    not_found.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_http_service_honors_retry_after() {
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("GET", "/limited")
+        .with_status(429)
+        .with_header("retry-after", "1")
+        .expect(1)
+        .create_async()
+        .await;
+    let ok = server.mock("GET", "/limited").with_status(200).expect(1).create_async().await;
+    let closed = server
+        .mock("GET", "/closed")
+        .with_status(429)
+        .with_header("retry-after", "3600")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = HttpService::with_config(HttpConfig {
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_secs(2),
+        ..HttpConfig::default()
+    })
+    .unwrap();
+    let started = Instant::now();
+    let response = client.get(format!("{}/limited", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(started.elapsed() >= Duration::from_secs(1), "waited for Retry-After, not the backoff");
+
+    let started = Instant::now();
+    let response = client.get(format!("{}/closed", server.url())).send().await.unwrap();
+    assert_eq!(response.status(), 429, "a Retry-After past max_delay is handed back");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    limited.assert_async().await;
+    ok.assert_async().await;
+    closed.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_http_service_limits_requests_per_host() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // A server that holds each request for a moment and records how many
+    // it was holding at once
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    {
+        let (in_flight, peak) = (in_flight.clone(), peak.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let (in_flight, peak) = (in_flight.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = socket.read(&mut buf).await;
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                        .await;
+                });
+            }
+        });
+    }
+
+    let client = HttpService::with_config(HttpConfig { per_host_limit: 2, ..HttpConfig::default() }).unwrap();
+    let url = format!("http://{}/slow", addr);
+    let responses = futures::future::join_all((0..6).map(|_| client.get(&url).send())).await;
+
+    assert!(responses.iter().all(|r| r.as_ref().is_ok_and(|r| r.status() == 200)));
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_http_service_rate_limits_hosts() {
+    let mut server = mockito::Server::new_async().await;
+    let ok = server.mock("GET", "/ok").with_status(200).expect(4).create_async().await;
+    let limited = server.mock("GET", "/limited").with_status(429).expect(1).create_async().await;
+
+    let client = fast_retries(0);
+    let url = format!("{}/ok", server.url());
+    let host = url::Url::parse(&url).unwrap().host_str().unwrap().to_string();
+    assert!(client.try_reserve(&host).is_some(), "hosts without a limit are never held back");
+
+    // Two back to back, then one every 100ms
+    client.set_rate_limit(&host, RateLimit { burst: 2, per_minute: 600 });
+    let started = Instant::now();
+    for _ in 0..4 {
+        assert_eq!(client.get(&url).send().await.unwrap().status(), 200);
+    }
+    assert!(started.elapsed() >= Duration::from_millis(150), "{:?}", started.elapsed());
+
+    // A reservation dropped unused gives its token back
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    let first = client.try_reserve(&host).unwrap();
+    let second = client.try_reserve(&host).unwrap();
+    assert!(client.try_reserve(&host).is_none(), "both tokens are set aside");
+    drop((first, second));
+    assert!(client.try_reserve(&host).is_some());
+
+    // A 429 spends whatever budget is left
+    client.get(format!("{}/limited", server.url())).send().await.unwrap();
+    assert!(client.try_reserve(&host).is_none());
+
+    ok.assert_async().await;
+    limited.assert_async().await;
+}
+
+/// Fetches one item from `url`, with a budget of one request a minute there
+struct BudgetedProvider {
+    url: String,
+}
+
+#[async_trait::async_trait]
+impl ContentProvider for BudgetedProvider {
+    fn name(&self) -> &str {
+        "budgeted"
+    }
+
+    fn category(&self) -> &str {
+        "joke"
+    }
+
+    async fn fetch(&self, client: &HttpService) -> Result<Vec<FetchedItem>, FetchError> {
+        let response = client.get(&self.url).send().await?;
+        Ok(vec![fetched_item("budgeted", "joke", &response.text().await?, &self.url)])
+    }
+
+    fn rate_limits(&self) -> Vec<HostLimit> {
+        let host = url::Url::parse(&self.url).unwrap().host_str().unwrap().to_string();
+        vec![HostLimit::new(&host, RateLimit { burst: 1, per_minute: 1 })]
+    }
+}
+
+#[tokio::test]
+async fn test_crawl_defers_providers_over_budget() {
+    let (db, _temp_dir) = create_test_db();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("GET", "/joke").with_status(200).with_body("A joke").expect(1).create_async().await;
+    let provider = BudgetedProvider { url: format!("{}/joke", server.url()) };
+    let client = HttpService::new().unwrap();
+
+    let crawl = || cazzmachine_lib::crawler::crawl_provider(&db, &client, &provider, Duration::from_secs(5));
+    assert_eq!(crawl().await, 1);
+    let started = Instant::now();
+    assert_eq!(crawl().await, 0);
+    assert!(started.elapsed() < Duration::from_secs(1), "deferred, not waiting for the bucket");
+    mock.assert_async().await;
+
+    let outcomes: Vec<(String, Option<String>)> = {
+        let conn = db.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT outcome, error FROM provider_runs ORDER BY started_at").unwrap();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    };
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].0, "success");
+    assert_eq!(outcomes[1].0, "deferred");
+    assert!(outcomes[1].1.as_deref().unwrap().contains("budget"));
+    assert!(db.get_recent_diagnostics(10).unwrap().iter().any(|d| d.event_type == "crawl_deferred"));
+}
+
+/// Providers sharing a host's last token don't both go ahead: one fetches,
+/// the other is deferred instead of waiting out its timeout for the bucket
+#[tokio::test]
+async fn test_crawl_reserves_budget_for_concurrent_providers() {
+    let (db, _temp_dir) = create_test_db();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("GET", "/joke").with_status(200).with_body("A joke").expect(1).create_async().await;
+    let provider = BudgetedProvider { url: format!("{}/joke", server.url()) };
+    let client = HttpService::new().unwrap();
+
+    let crawl = || cazzmachine_lib::crawler::crawl_provider(&db, &client, &provider, Duration::from_secs(5));
+    let started = Instant::now();
+    let (first, second) = tokio::join!(crawl(), crawl());
+    assert_eq!(first + second, 1);
+    assert!(started.elapsed() < Duration::from_secs(1), "{:?}", started.elapsed());
+    mock.assert_async().await;
+
+    let mut outcomes: Vec<String> = {
+        let conn = db.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT outcome FROM provider_runs").unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    };
+    outcomes.sort();
+    assert_eq!(outcomes, ["deferred", "success"]);
+}
+
+#[test]
+fn test_reddit_providers_share_a_budget() {
+    use cazzmachine_lib::crawler::registry::ProviderRegistry;
+
+    let registry = ProviderRegistry::builtin();
+    let reddit_backed = ["memes", "reddit-memes", "reddit-videos", "gossip"];
+    for name in reddit_backed {
+        let limits = registry.get(name).unwrap().rate_limits();
+        assert!(limits.iter().any(|l| l.host == "reddit.com"), "{} has no reddit.com budget", name);
+    }
+    assert!(registry.get("hackernews").unwrap().rate_limits().is_empty());
+}
+
+/// Overrides from settings replace a provider's default budget on a host,
+/// add hosts it doesn't declare and leave other providers alone
+#[test]
+fn test_rate_limit_overrides_per_provider() {
+    use cazzmachine_lib::crawler::limits::RateLimitOverrides;
+    use cazzmachine_lib::crawler::reddit::{API_LIMIT, MEDIA_LIMIT};
+    use cazzmachine_lib::crawler::registry::ProviderRegistry;
+    use cazzmachine_lib::settings::Settings;
+
+    let registry = ProviderRegistry::builtin();
+    let overrides: RateLimitOverrides = serde_json::from_str(
+        r#"{"gossip": {"Reddit.com": {"burst": 0, "per_minute": 5000}, "i.imgur.com": {"burst": 5, "per_minute": 20}}}"#,
+    )
+    .unwrap();
+    let overrides = overrides.clamped();
+
+    let gossip = overrides.limits_for(registry.get("gossip").unwrap());
+    assert_eq!(
+        gossip,
+        [
+            HostLimit::new("reddit.com", RateLimit { burst: 1, per_minute: 600 }),
+            HostLimit::new("redd.it", MEDIA_LIMIT),
+            HostLimit::new("i.imgur.com", RateLimit { burst: 5, per_minute: 20 }),
+        ]
+    );
+    let memes = overrides.limits_for(registry.get("memes").unwrap());
+    assert_eq!(memes[0], HostLimit::new("reddit.com", API_LIMIT), "defaults stay for other providers");
+
+    // Overrides are a setting like any other
+    let (db, _temp_dir) = create_test_db();
+    Settings { rate_limits: overrides.clone(), ..Settings::default() }.save(&db).unwrap();
+    assert_eq!(Settings::load(&db).unwrap().rate_limits, overrides);
+}
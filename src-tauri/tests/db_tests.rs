@@ -3,8 +3,9 @@
 //! These tests verify that the deadlock fix (using log_diagnostic_with_conn instead
 //! of log_diagnostic_event while holding the mutex) works correctly.
 
-use cazzmachine_lib::db::models::CrawlItem;
+use cazzmachine_lib::db::models::{CrawlItem, ProviderRun};
 use cazzmachine_lib::db::Database;
+use cazzmachine_lib::images::ImageData;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -29,13 +30,14 @@ fn create_test_item(id_suffix: &str, category: &str) -> CrawlItem {
         title: format!("Test {} item {}", category, id_suffix),
         url: format!("https://example.com/{}/{}", category, id_suffix),
         thumbnail_url: Some("https://example.com/thumb.jpg".to_string()),
-        thumbnail_data: None,
+        thumbnail_hash: None,
         description: Some(format!("Test description for {} item", category)),
         fetched_at: timestamp,
         is_seen: false,
         is_saved: false,
         is_consumed: false,
         session_date: today,
+        metadata: Default::default(),
     }
 }
 
@@ -396,3 +398,612 @@ fn test_provider_status() {
         );
     }
 }
+
+/// Settings survive a reopen of the database
+#[test]
+fn test_settings_persist_across_reopen() {
+    use cazzmachine_lib::settings::Settings;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    {
+        let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(Settings::load(&db).unwrap(), Settings::default());
+
+        Settings {
+            throttle_level: 8,
+            consumption_threads: 42,
+            thumbnail_max_px: 10,
+            ..Settings::default()
+        }
+        .clamped()
+        .save(&db)
+        .unwrap();
+    }
+
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+    let loaded = Settings::load(&db).unwrap();
+    assert_eq!(loaded.throttle_level, 8);
+    assert_eq!(loaded.consumption_threads, 8, "Thread count is clamped to 8");
+    assert_eq!(loaded.thumbnail_max_px, 64, "Thumbnails are at least 64px");
+
+    db.set_app_state("throttle_level", "not-a-number").unwrap();
+    assert_eq!(Settings::load(&db).unwrap().throttle_level, Settings::default().throttle_level);
+}
+
+fn provider_run(provider: &str, started_at: &str, duration_ms: i64, outcome: &str) -> ProviderRun {
+    ProviderRun {
+        provider: provider.to_string(),
+        started_at: started_at.to_string(),
+        duration_ms,
+        outcome: outcome.to_string(),
+        items_fetched: if outcome == "success" { 5 } else { 0 },
+        items_new: if outcome == "success" { 3 } else { 0 },
+        error: (outcome != "success").then(|| format!("{} failure", outcome)),
+        status_code: (outcome == "rate_limited").then_some(429),
+    }
+}
+
+/// Provider health is derived from provider_runs, matched by exact provider name
+#[test]
+fn test_provider_status_from_runs() {
+    let (db, _temp_dir) = create_test_db();
+
+    db.record_provider_run(&provider_run("memes", "2024-01-01T10:00:00", 100, "success")).unwrap();
+    db.record_provider_run(&provider_run("memes", "2024-01-01T10:05:00", 300, "success")).unwrap();
+    db.record_provider_run(&provider_run("memes", "2024-01-01T10:10:00", 200, "network")).unwrap();
+    db.record_provider_run(&provider_run("memes", "2024-01-01T10:15:00", 400, "rate_limited")).unwrap();
+    db.record_provider_run(&provider_run("reddit-memes", "2024-01-01T10:20:00", 50, "success")).unwrap();
+
+    let statuses = db.get_provider_status().unwrap();
+
+    let memes = statuses.iter().find(|s| s.provider_name == "memes").unwrap();
+    assert_eq!(memes.last_fetch_status, "error");
+    assert_eq!(memes.total_runs, 4);
+    assert!((memes.success_rate - 0.5).abs() < f64::EPSILON);
+    assert_eq!(memes.median_latency_ms, Some(250));
+    assert_eq!(memes.consecutive_failures, 2);
+    assert_eq!(memes.last_error.as_deref(), Some("rate_limited failure"));
+    assert_eq!(memes.last_status_code, Some(429));
+
+    let reddit_memes = statuses.iter().find(|s| s.provider_name == "reddit-memes").unwrap();
+    assert_eq!(reddit_memes.last_fetch_status, "ok");
+    assert_eq!(reddit_memes.total_runs, 1, "'memes' runs must not leak into 'reddit-memes'");
+    assert_eq!(reddit_memes.consecutive_failures, 0);
+
+    let never_ran = statuses.iter().find(|s| s.provider_name == "hackernews").unwrap();
+    assert_eq!(never_ran.last_fetch_status, "unknown");
+    assert_eq!(never_ran.median_latency_ms, None);
+}
+
+/// Deferred runs never reached the network, so they show in the status but
+/// not in the health figures
+#[test]
+fn test_deferred_runs_do_not_count_against_health() {
+    let (db, _temp_dir) = create_test_db();
+
+    db.record_provider_run(&provider_run("gossip", "2024-01-01T10:00:00", 100, "success")).unwrap();
+    db.record_provider_run(&provider_run("gossip", "2024-01-01T10:05:00", 0, "deferred")).unwrap();
+    db.record_provider_run(&provider_run("gossip", "2024-01-01T10:10:00", 0, "deferred")).unwrap();
+
+    let statuses = db.get_provider_status().unwrap();
+    let gossip = statuses.iter().find(|s| s.provider_name == "gossip").unwrap();
+    assert_eq!(gossip.last_fetch_status, "deferred");
+    assert_eq!(gossip.last_fetch_timestamp.as_deref(), Some("2024-01-01T10:00:00"));
+    assert_eq!(gossip.total_runs, 1);
+    assert_eq!(gossip.recent_error_count, 0);
+    assert_eq!(gossip.consecutive_failures, 0);
+    assert_eq!(gossip.median_latency_ms, Some(100));
+}
+
+#[tokio::test]
+async fn test_buffer_manager_skips_crawl_when_buffer_full() {
+    use cazzmachine_lib::net::HttpService;
+    use cazzmachine_lib::shared::buffer_manager::BufferManager;
+
+    let (db, _temp_dir) = create_test_db();
+    // Default settings (level 5, one thread) target 75 pending items
+    for i in 0..100 {
+        db.insert_item(&create_test_item(&i.to_string(), "joke")).unwrap();
+    }
+    let db = Arc::new(db);
+
+    let added = BufferManager::new(db.clone(), Arc::new(HttpService::new().unwrap())).replenish_buffer_if_needed().await.unwrap();
+
+    assert_eq!(added, 0);
+    assert_eq!(db.get_pending_count().unwrap(), 100);
+}
+
+#[test]
+fn test_sources_crud_and_registry() {
+    use cazzmachine_lib::crawler::registry::ProviderRegistry;
+
+    let (db, _temp_dir) = create_test_db();
+    let builtin_count = ProviderRegistry::builtin().len();
+
+    let sub = db.add_source("subreddit", "aww", "meme").unwrap();
+    let feed = db.add_source("feed", "https://blog.example.com/atom.xml", "news").unwrap();
+    assert!(db.add_source("subreddit", "aww", "meme").is_err(), "duplicate sources are rejected");
+
+    let registry = ProviderRegistry::load(&db);
+    assert_eq!(registry.len(), builtin_count + 2);
+    assert_eq!(registry.get("aww").unwrap().category(), "meme");
+    assert_eq!(registry.get("https://blog.example.com/atom.xml").unwrap().category(), "news");
+
+    assert!(db.set_source_enabled(&sub.id, false).unwrap());
+    assert!(ProviderRegistry::load(&db).get("aww").is_none());
+    assert!(!db.list_sources().unwrap()[0].enabled);
+
+    assert!(db.remove_source(&feed.id).unwrap());
+    assert!(!db.remove_source(&feed.id).unwrap());
+    assert_eq!(db.list_sources().unwrap().len(), 1);
+    assert_eq!(ProviderRegistry::load(&db).len(), builtin_count);
+}
+
+#[test]
+fn test_search_items_ranks_and_stays_in_sync() {
+    use cazzmachine_lib::db::models::{Paging, SearchFilters};
+
+    let (db, _temp_dir) = create_test_db();
+    let mut kube_meme = create_test_item("kube", "meme");
+    kube_meme.title = "When the Kubernetes pod restarts again".to_string();
+    let mut kube_news = create_test_item("kube-news", "news");
+    kube_news.title = "Cloud outage".to_string();
+    kube_news.description = Some("A kubernetes upgrade went wrong".to_string());
+    let mut other = create_test_item("other", "joke");
+    other.title = "<img src=x onerror=alert(1)> & friends".to_string();
+    for item in [&kube_meme, &kube_news, &other] {
+        db.insert_item(item).unwrap();
+    }
+
+    let all = SearchFilters::default();
+    let page = Paging::default();
+    assert_eq!(db.search_items("kubernetes", &all, &page).unwrap().total, 0, "pending items are not searchable");
+
+    db.consume_pending_items(1000.0).unwrap();
+    let results = db.search_items("kube", &all, &page).unwrap();
+    assert_eq!(results.total, 2);
+    assert_eq!(results.hits[0].item.id, kube_meme.id, "title matches rank above description matches");
+    assert!(results.hits[0].title_highlighted.contains("<mark>Kubernetes</mark>"));
+    assert!(results.hits[1].snippet.contains("<mark>kubernetes</mark>"));
+    let markup = db.search_items("friends", &all, &page).unwrap();
+    assert_eq!(
+        markup.hits[0].title_highlighted,
+        "&lt;img src=x onerror=alert(1)&gt; &amp; <mark>friends</mark>",
+        "crawled text is escaped"
+    );
+
+    let news_only = SearchFilters { category: Some("news".to_string()), ..Default::default() };
+    assert_eq!(db.search_items("kube", &news_only, &page).unwrap().total, 1);
+    let second_page = db.search_items("kube", &all, &Paging { offset: 1, limit: 1 }).unwrap();
+    assert_eq!(second_page.total, 2);
+    assert_eq!(second_page.hits.len(), 1);
+    assert_eq!(second_page.hits[0].item.id, kube_news.id);
+
+    assert_eq!(db.search_items("\"unbalanced AND (", &all, &page).unwrap().total, 0);
+    assert_eq!(db.search_items("   ", &all, &page).unwrap().total, 0);
+
+    // Last week's items are archived by pruning and still found by title,
+    // but not by the description pruning dropped
+    let last_week = (chrono::Local::now() - chrono::Duration::days(7)).format("%Y-%m-%d").to_string();
+    {
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET session_date = ?1", [&last_week]).unwrap();
+    }
+    db.prune_old_items().unwrap();
+    let results = db.search_items("kubernetes", &all, &page).unwrap();
+    assert_eq!(results.total, 1);
+    assert_eq!(results.hits[0].item.id, kube_meme.id);
+    assert_eq!(results.hits[0].item.session_date, last_week);
+    assert_eq!(results.hits[0].item.title, kube_meme.title, "archived items keep their title");
+    assert_eq!(results.hits[0].item.description, None);
+    let last_week_only = SearchFilters { from_date: Some(last_week.clone()), to_date: Some(last_week), ..Default::default() };
+    assert_eq!(db.search_items("kubernetes pod", &last_week_only, &page).unwrap().total, 1);
+}
+
+/// Saved items are kept intact by pruning and browsable as a library
+#[test]
+fn test_saved_items_survive_pruning_in_library() {
+    use cazzmachine_lib::db::models::{LibraryFilters, Paging};
+
+    let (db, _temp_dir) = create_test_db();
+    let saved_meme = create_test_item("saved-meme", "meme");
+    let saved_news = create_test_item("saved-news", "news");
+    let unsaved = create_test_item("unsaved", "joke");
+    let saved_pending = create_test_item("saved-pending", "video");
+    for item in [&saved_meme, &saved_news, &unsaved, &saved_pending] {
+        db.insert_item(item).unwrap();
+    }
+    db.consume_pending_items(1000.0).unwrap();
+    {
+        let conn = db.conn.lock().unwrap();
+        // Still unconsumed: pruning would otherwise delete it outright
+        conn.execute("UPDATE crawl_items SET is_consumed = 0 WHERE id = ?1", [&saved_pending.id])
+            .unwrap();
+    }
+    for item in [&saved_meme, &saved_news, &saved_pending] {
+        assert!(db.toggle_item_saved(&item.id).unwrap());
+    }
+    {
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET session_date = '2000-01-01'", []).unwrap();
+        conn.execute("UPDATE library SET saved_at = '2025-03-01 10:00:00' WHERE item_id = ?1", [&saved_meme.id])
+            .unwrap();
+        conn.execute("UPDATE library SET saved_at = '2025-03-05 10:00:00' WHERE item_id = ?1", [&saved_news.id])
+            .unwrap();
+        conn.execute("UPDATE library SET saved_at = '2025-03-09 10:00:00' WHERE item_id = ?1", [&saved_pending.id])
+            .unwrap();
+    }
+
+    db.prune_old_items().unwrap();
+
+    let all = LibraryFilters::default();
+    let library = db.get_saved_items(&all, &Paging::default()).unwrap();
+    assert_eq!(library.total, 3);
+    let ids: Vec<&str> = library.items.iter().map(|e| e.item.id.as_str()).collect();
+    assert_eq!(ids, [saved_pending.id.as_str(), saved_news.id.as_str(), saved_meme.id.as_str()]);
+    let meme = library.items.iter().find(|e| e.item.id == saved_meme.id).unwrap();
+    assert_eq!(meme.item.title, saved_meme.title, "saved items are not archived");
+    assert_eq!(meme.item.description, saved_meme.description);
+
+    let archived: Option<String> = db
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT description FROM crawl_items WHERE id = ?1", [&unsaved.id], |row| row.get(0))
+        .unwrap();
+    assert_eq!(archived, None);
+
+    // Paging and filters
+    let second = db.get_saved_items(&all, &Paging { offset: 1, limit: 1 }).unwrap();
+    assert_eq!(second.total, 3);
+    assert_eq!(second.items[0].item.id, saved_news.id);
+    let news = LibraryFilters { category: Some("news".to_string()), ..Default::default() };
+    assert_eq!(db.get_saved_items(&news, &Paging::default()).unwrap().total, 1);
+    let early_march = LibraryFilters {
+        from_date: Some("2025-03-01".to_string()),
+        to_date: Some("2025-03-05".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(db.get_saved_items(&early_march, &Paging::default()).unwrap().total, 2);
+
+    // Notes and tags
+    assert!(db.set_library_note(&saved_meme.id, Some("  classic  ")).unwrap());
+    assert!(!db.set_library_note(&unsaved.id, Some("nope")).unwrap());
+    let tags = db
+        .set_library_tags(&saved_meme.id, &["Cats".to_string(), " cats".to_string(), "".to_string(), "work".to_string()])
+        .unwrap();
+    assert_eq!(tags, Some(vec!["cats".to_string(), "work".to_string()]));
+    assert_eq!(db.set_library_tags(&unsaved.id, &["x".to_string()]).unwrap(), None);
+
+    let cats = LibraryFilters { tag: Some("cats".to_string()), ..Default::default() };
+    let tagged = db.get_saved_items(&cats, &Paging::default()).unwrap();
+    assert_eq!(tagged.total, 1);
+    assert_eq!(tagged.items[0].note.as_deref(), Some("classic"));
+    assert_eq!(tagged.items[0].tags, ["cats", "work"]);
+
+    // Unsaving drops the library entry with its tags
+    assert!(!db.toggle_item_saved(&saved_meme.id).unwrap());
+    assert_eq!(db.get_saved_items(&cats, &Paging::default()).unwrap().total, 0);
+    assert_eq!(db.get_saved_items(&all, &Paging::default()).unwrap().total, 2);
+}
+
+/// Finished days are rolled up into session_stats and charted as a series
+#[test]
+fn test_stats_rollup_and_history() {
+    use cazzmachine_lib::db::models::StatsPeriod;
+
+    let (db, _temp_dir) = create_test_db();
+    let today = chrono::Local::now().date_naive();
+    let two_days_ago = (today - chrono::Duration::days(2)).format("%Y-%m-%d").to_string();
+    let long_ago = (today - chrono::Duration::days(40)).format("%Y-%m-%d").to_string();
+
+    let mut old_meme = create_test_item("old-meme", "meme");
+    old_meme.session_date = two_days_ago.clone();
+    let mut old_video = create_test_item("old-video", "video");
+    old_video.session_date = two_days_ago.clone();
+    old_video.source = "test-other-source".to_string();
+    let mut ancient_joke = create_test_item("ancient-joke", "joke");
+    ancient_joke.session_date = long_ago.clone();
+    let todays_news = create_test_item("news", "news");
+    for item in [&old_meme, &old_video, &ancient_joke, &todays_news] {
+        db.insert_item(item).unwrap();
+    }
+    {
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET is_consumed = 1 WHERE session_date < date('now', 'localtime')", [])
+            .unwrap();
+    }
+    db.consume_pending_items(10.0).unwrap();
+    db.consume_pending_items(10.0).unwrap();
+
+    assert_eq!(db.roll_up_stats().unwrap(), 2);
+    assert_eq!(db.roll_up_stats().unwrap(), 0, "rolled-up days are not written twice");
+
+    // Pruning strips archived items but the rollup keeps their counts
+    db.prune_old_items().unwrap();
+
+    let week = db.get_stats_series(StatsPeriod::Week).unwrap();
+    assert_eq!(week.len(), 7);
+    assert_eq!(week.last().unwrap().date, today.format("%Y-%m-%d").to_string());
+    let old_day = week.iter().find(|d| d.date == two_days_ago).unwrap();
+    assert_eq!(old_day.stats.memes_found, 1);
+    assert_eq!(old_day.stats.videos_found, 1);
+    assert_eq!(old_day.stats.estimated_time_saved_minutes, 3.5);
+    assert_eq!(old_day.providers_used, 2);
+    let today_stats = week.last().unwrap();
+    assert_eq!(today_stats.stats.news_checked, 1);
+    assert_eq!(today_stats.consume_calls, 2);
+    assert_eq!(week.iter().filter(|d| d.stats.total_items == 0).count(), 5, "idle days are zero-filled");
+
+    let summary = db.get_stats_summary(StatsPeriod::Week).unwrap();
+    assert_eq!(summary.days_active, 2);
+    assert_eq!(summary.totals.total_items, 3);
+    assert_eq!(summary.consume_calls, 2);
+    assert_eq!(summary.totals.estimated_time_saved_minutes, 5.5);
+
+    let all_time = db.get_stats_summary(StatsPeriod::All).unwrap();
+    assert_eq!(all_time.from_date, long_ago);
+    assert_eq!(all_time.totals.total_items, 4);
+    assert_eq!(db.get_stats_series(StatsPeriod::Month).unwrap().len(), 30);
+}
+
+/// The consume call delegates item choice to the selected strategy
+#[test]
+fn test_consume_with_strategies() {
+    use cazzmachine_lib::consumption::{CostModel, EngagementFirstStrategy, RoundRobinStrategy, StrategyKind};
+    use cazzmachine_lib::settings::Settings;
+
+    let (db, _temp_dir) = create_test_db();
+    // Three memes fetched before one joke: FIFO would spend a 1.5min budget on memes only
+    for (i, category) in ["meme", "meme", "meme", "joke"].iter().enumerate() {
+        let mut item = create_test_item(&i.to_string(), category);
+        item.fetched_at = format!("{} 10:00:0{}", item.session_date, i);
+        db.insert_item(&item).unwrap();
+    }
+
+    let result = db.consume_pending_items_with(0.8, &RoundRobinStrategy, &CostModel::default()).unwrap();
+    assert_eq!(result.strategy, "round_robin");
+    assert_eq!((result.jokes_consumed, result.memes_consumed), (1, 1));
+    assert_eq!(result.items_discarded, 2);
+
+    // A saved item from the same source makes the remaining memes most engaging
+    let mut favourite = create_test_item("favourite", "meme");
+    favourite.session_date = "2000-01-01".to_string();
+    db.insert_item(&favourite).unwrap();
+    db.toggle_item_saved(&favourite.id).unwrap();
+    let mut other = create_test_item("other", "gossip");
+    other.source = "elsewhere".to_string();
+    db.insert_item(&other).unwrap();
+    let result = db.consume_pending_items_with(0.5, &EngagementFirstStrategy, &CostModel::default()).unwrap();
+    assert_eq!((result.memes_consumed, result.gossip_consumed), (1, 0));
+
+    // The setting is persisted (saved without applying, as other tests share the process)
+    Settings {
+        consumption_strategy: StrategyKind::Random,
+        ..Settings::default()
+    }
+    .save(&db)
+    .unwrap();
+    assert_eq!(Settings::load(&db).unwrap().consumption_strategy, StrategyKind::Random);
+    db.set_app_state("consumption_strategy", "lifo").unwrap();
+    assert_eq!(Settings::load(&db).unwrap().consumption_strategy, StrategyKind::Fifo);
+}
+
+/// Costs come from the cost model passed in, not a fixed table
+#[test]
+fn test_consume_with_custom_cost_model() {
+    use cazzmachine_lib::consumption::{CostModel, FifoStrategy};
+
+    let (db, _temp_dir) = create_test_db();
+    let mut joke = create_test_item("slow", "joke");
+    joke.source = "slow-jokes".to_string();
+    joke.fetched_at = format!("{} 10:00:00", joke.session_date);
+    let mut news = create_test_item("short", "news");
+    news.fetched_at = format!("{} 10:00:01", news.session_date);
+    db.insert_item(&joke).unwrap();
+    db.insert_item(&news).unwrap();
+
+    let mut model = CostModel {
+        reading_words_per_minute: Some(200.0),
+        ..CostModel::default()
+    };
+    model.sources.insert("slow-jokes".to_string(), 5.0);
+
+    let result = db.consume_pending_items_with(1.0, &FifoStrategy, &model).unwrap();
+    assert_eq!((result.jokes_consumed, result.news_consumed), (0, 1));
+    assert!(result.time_consumed_minutes < 0.1, "nine words read at 200 wpm");
+
+    // The same items under the default table
+    let (db, _temp_dir) = create_test_db();
+    db.insert_item(&joke).unwrap();
+    db.insert_item(&news).unwrap();
+    let result = db.consume_pending_items_with(1.0, &FifoStrategy, &CostModel::default()).unwrap();
+    assert_eq!((result.jokes_consumed, result.news_consumed), (1, 0));
+    assert_eq!(result.time_consumed_minutes, 0.3);
+}
+
+/// Provider metadata is stored with the item, and a known video length is
+/// what consuming it costs
+#[test]
+fn test_item_metadata_round_trip_and_video_cost() {
+    use cazzmachine_lib::consumption::{CostModel, FifoStrategy};
+    use cazzmachine_lib::db::models::ItemMetadata;
+
+    let (db, _temp_dir) = create_test_db();
+    let mut short_video = create_test_item("short", "video");
+    short_video.metadata = ItemMetadata {
+        score: Some(812),
+        comments: Some(40),
+        author: Some("someone".to_string()),
+        published_at: Some("2025-06-10 06:00:00".to_string()),
+        media_duration_secs: Some(30.0),
+        domain: Some("v.redd.it".to_string()),
+    };
+    db.insert_item(&short_video).unwrap();
+    db.insert_item(&create_test_item("unknown-length", "video")).unwrap();
+
+    let result = db.consume_pending_items_with(1.0, &FifoStrategy, &CostModel::default()).unwrap();
+    assert_eq!(result.videos_consumed, 1, "only the 30s video fits in a minute");
+    assert_eq!(result.time_consumed_minutes, 0.5);
+
+    let items = db.get_items_for_today().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].metadata, short_video.metadata);
+}
+
+/// A wavy pattern as a thumbnail, `size` pixels square. The pattern is the
+/// same at any size.
+fn pattern_thumbnail(size: u32, format: image::ImageFormat) -> ImageData {
+    let image = image::RgbImage::from_fn(size, size, |x, y| {
+        let (u, v) = (x as f32 / size as f32, y as f32 / size as f32);
+        let shade = ((u * 9.0).sin() * (v * 5.0).cos() * 120.0 + 128.0) as u8;
+        image::Rgb([shade, shade / 2, 255 - shade])
+    });
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(image).write_to(&mut bytes, format).unwrap();
+    ImageData {
+        mime: format.to_mime_type().to_string(),
+        bytes: bytes.into_inner(),
+    }
+}
+
+/// Copies by URL, reworded title or re-encoded image are linked to the first
+/// item instead of being stored
+#[test]
+fn test_insert_item_deduped() {
+    use cazzmachine_lib::crawler::dedupe::Fingerprint;
+    use cazzmachine_lib::db::models::InsertOutcome;
+
+    let (db, _temp_dir) = create_test_db();
+    let insert_with = |item: &CrawlItem, thumbnail: Option<&ImageData>| {
+        db.insert_item_deduped(item, &Fingerprint::of(item, thumbnail), thumbnail)
+            .unwrap()
+    };
+    let insert = |item: &CrawlItem| insert_with(item, None);
+    let reason = |outcome: InsertOutcome| match outcome {
+        InsertOutcome::Duplicate { reason, .. } => reason,
+        other => panic!("expected a duplicate, got {:?}", other),
+    };
+
+    let mut story = create_test_item("story", "news");
+    story.title = "Scientists discover a new species of deep sea octopus".to_string();
+    story.url = "https://www.example.com/science/octopus".to_string();
+    assert_eq!(insert(&story), InsertOutcome::Inserted);
+    assert_eq!(insert(&story), InsertOutcome::AlreadyStored);
+
+    let mut tracked = create_test_item("tracked", "news");
+    tracked.url = "https://example.com/science/octopus/?utm_source=rss".to_string();
+    assert_eq!(reason(insert(&tracked)), "url");
+
+    let mut reworded = create_test_item("reworded", "news");
+    reworded.source = "BBC News".to_string();
+    reworded.title = "Scientists discover new species of deep-sea octopus!".to_string();
+    assert_eq!(reason(insert(&reworded)), "title");
+    assert_eq!(insert(&reworded), InsertOutcome::AlreadyStored, "a known copy is not recorded twice");
+
+    let mut other = create_test_item("other", "news");
+    other.title = "Local council approves budget for new cycling lanes".to_string();
+    assert_eq!(insert(&other), InsertOutcome::Inserted);
+
+    let meme_image = pattern_thumbnail(64, image::ImageFormat::Png);
+    let mut meme = create_test_item("meme", "meme");
+    meme.thumbnail_hash = Some(meme_image.hash());
+    assert_eq!(insert_with(&meme, Some(&meme_image)), InsertOutcome::Inserted);
+    let crosspost_image = pattern_thumbnail(48, image::ImageFormat::Jpeg);
+    let mut crosspost = create_test_item("crosspost", "meme");
+    crosspost.thumbnail_hash = Some(crosspost_image.hash());
+    assert_eq!(reason(insert_with(&crosspost, Some(&crosspost_image))), "image");
+    assert_eq!(db.get_image(&meme_image.hash()).unwrap(), Some(meme_image));
+    assert_eq!(db.get_image(&crosspost_image.hash()).unwrap(), None, "copies' thumbnails are not stored");
+
+    let duplicates = db.get_item_duplicates(&story.id).unwrap();
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(duplicates[1].source, "BBC News");
+    assert!(duplicates[1].similarity >= 0.7);
+    assert_eq!(db.get_item_duplicates(&meme.id).unwrap()[0].id, crosspost.id);
+
+    let stored: i64 = db
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM crawl_items", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(stored, 3);
+}
+
+/// Fingerprints computed off the runtime agree with the synchronous ones
+#[tokio::test]
+async fn test_fingerprint_compute_matches_of() {
+    use cazzmachine_lib::crawler::dedupe::Fingerprint;
+
+    let image = pattern_thumbnail(64, image::ImageFormat::Png);
+    let item = create_test_item("meme", "meme");
+    let fingerprint = Fingerprint::compute(&item, Some(&image)).await;
+    assert!(fingerprint.image_dhash.is_some());
+    assert_eq!(fingerprint, Fingerprint::of(&item, Some(&image)));
+    assert_eq!(Fingerprint::compute(&item, None).await, Fingerprint::of(&item, None));
+}
+
+/// Images no item references go first, then the least recently used, but
+/// never the thumbnails of saved items
+#[test]
+fn test_image_store_eviction() {
+    let (db, _temp_dir) = create_test_db();
+    let image = |fill: u8| ImageData { mime: "image/png".to_string(), bytes: vec![fill; 100] };
+
+    let mut items = Vec::new();
+    for (name, fill, last_used) in [("saved", 1, "2000-01-01"), ("old", 2, "2000-01-02"), ("recent", 3, "2000-01-03")] {
+        let mut item = create_test_item(name, "meme");
+        let hash = db.store_image(&image(fill)).unwrap();
+        item.thumbnail_hash = Some(hash.clone());
+        db.insert_item(&item).unwrap();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE images SET last_used_at = ?1 WHERE hash = ?2", [last_used, hash.as_str()])
+            .unwrap();
+        items.push(item);
+    }
+    db.toggle_item_saved(&items[0].id).unwrap();
+    assert_eq!(db.store_image(&image(1)).unwrap(), image(1).hash(), "same bytes, same hash");
+    db.store_image(&image(4)).unwrap();
+    assert_eq!(db.image_store_size().unwrap(), 400);
+
+    assert_eq!(db.evict_images(1000).unwrap(), 1, "only the unreferenced image goes");
+    assert_eq!(db.get_image(&image(4).hash()).unwrap(), None);
+
+    assert_eq!(db.evict_images(250).unwrap(), 1);
+    assert_eq!(db.get_image(&image(2).hash()).unwrap(), None, "least recently used first");
+    assert!(db.get_image(&image(3).hash()).unwrap().is_some());
+
+    assert_eq!(db.evict_images(0).unwrap(), 1);
+    assert_eq!(db.get_image(&image(1).hash()).unwrap(), Some(image(1)), "saved thumbnails are kept");
+    assert_eq!(db.image_store_size().unwrap(), 100);
+
+    let thumbnails: Vec<Option<String>> = {
+        let conn = db.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT thumbnail_hash FROM crawl_items ORDER BY rowid").unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        rows
+    };
+    assert_eq!(thumbnails, [Some(image(1).hash()), None, None], "evicted thumbnails are unlinked");
+}
+
+/// Pruning unlinks the thumbnails of archived items, which frees their images
+#[test]
+fn test_prune_frees_archived_thumbnails() {
+    let (db, _temp_dir) = create_test_db();
+    let thumbnail = ImageData { mime: "image/jpeg".to_string(), bytes: vec![7; 64] };
+    let mut item = create_test_item("archived", "meme");
+    item.thumbnail_hash = Some(db.store_image(&thumbnail).unwrap());
+    db.insert_item(&item).unwrap();
+    db.conn
+        .lock()
+        .unwrap()
+        .execute("UPDATE crawl_items SET is_consumed = 1, session_date = '2000-01-01'", [])
+        .unwrap();
+
+    assert_eq!(db.prune_old_items().unwrap(), (0, 1));
+    assert_eq!(db.get_image(&thumbnail.hash()).unwrap(), None);
+    assert_eq!(db.image_store_size().unwrap(), 0);
+}
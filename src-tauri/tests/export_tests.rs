@@ -0,0 +1,301 @@
+//! Export tests
+//!
+//! Each test fills a fresh database, exports it to a temp dir and checks the
+//! files that come out.
+
+use cazzmachine_lib::db::models::CrawlItem;
+use cazzmachine_lib::db::Database;
+use cazzmachine_lib::export::{self, ExportFormat};
+use cazzmachine_lib::images::ImageData;
+
+fn item(id: &str, category: &str, title: &str, session_date: &str) -> CrawlItem {
+    CrawlItem {
+        id: id.to_string(),
+        source: format!("{}-source", category),
+        category: category.to_string(),
+        title: title.to_string(),
+        url: format!("https://example.com/{}", id),
+        thumbnail_url: None,
+        thumbnail_hash: Some("ab".repeat(32)),
+        description: None,
+        fetched_at: format!("{} 10:00:00", session_date),
+        is_seen: false,
+        is_saved: false,
+        is_consumed: false,
+        session_date: session_date.to_string(),
+        metadata: Default::default(),
+    }
+}
+
+/// Two consumed days in March 2025 and one in February; one saved item per March day.
+fn populated_db() -> (Database, tempfile::TempDir) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+
+    for entry in [
+        item("feb", "news", "February news", "2025-02-20"),
+        item("meme-1", "meme", "Cat, \"loaf\" edition", "2025-03-01"),
+        item("joke-1", "joke", "Why [did] the chicken", "2025-03-01"),
+        item("news-1", "news", "Markets do a thing", "2025-03-02"),
+    ] {
+        db.insert_item(&entry).unwrap();
+    }
+    db.toggle_item_saved("meme-1").unwrap();
+    db.toggle_item_saved("news-1").unwrap();
+    {
+        let conn = db.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET is_consumed = 1", []).unwrap();
+        conn.execute("UPDATE library SET saved_at = '2025-03-01 12:00:00' WHERE item_id = 'meme-1'", [])
+            .unwrap();
+        conn.execute("UPDATE library SET saved_at = '2025-03-02 12:00:00' WHERE item_id = 'news-1'", [])
+            .unwrap();
+    }
+    db.set_library_note("meme-1", Some("best loaf")).unwrap();
+    db.set_library_tags("meme-1", &["cats".to_string(), "loaf".to_string()]).unwrap();
+    (db, temp_dir)
+}
+
+#[test]
+fn test_json_export_covers_the_range() {
+    let (db, temp_dir) = populated_db();
+    let dest = temp_dir.path().join("exports/march.json");
+
+    let report = export::export(&db, ExportFormat::Json, &dest, Some("2025-03-01"), Some("2025-03-02")).unwrap();
+    assert_eq!(report.files, [dest.to_string_lossy().to_string()]);
+    assert_eq!((report.saved_items, report.history_items, report.days), (2, 3, 2));
+
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+    assert_eq!(json["saved"][0]["item"]["id"], "news-1", "most recently saved first");
+    assert_eq!(json["saved"][1]["tags"], serde_json::json!(["cats", "loaf"]));
+    assert!(json["history"][0]["item"].is_null());
+    assert!(json["history"][0]["thumbnail_hash"].is_null(), "thumbnails are left out");
+    assert_eq!(json["daily_stats"][0]["date"], "2025-03-01");
+    assert_eq!(json["daily_stats"][0]["stats"]["total_items"], 2);
+}
+
+#[test]
+fn test_csv_export_writes_one_file_per_table() {
+    let (db, temp_dir) = populated_db();
+    let dest = temp_dir.path().join("march.csv");
+
+    let report = export::export(&db, ExportFormat::Csv, &dest, Some("2025-03-01"), None).unwrap();
+    assert_eq!(report.files.len(), 3);
+
+    let saved = std::fs::read_to_string(temp_dir.path().join("march-saved.csv")).unwrap();
+    let lines: Vec<&str> = saved.lines().collect();
+    assert_eq!(lines[0], "saved_at,category,source,title,url,note,tags");
+    assert_eq!(
+        lines[2],
+        "2025-03-01 12:00:00,meme,meme-source,\"Cat, \"\"loaf\"\" edition\",https://example.com/meme-1,best loaf,cats;loaf"
+    );
+
+    let history = std::fs::read_to_string(temp_dir.path().join("march-history.csv")).unwrap();
+    assert_eq!(history.lines().count(), 4, "header plus the three March items");
+    assert!(!history.contains("February"));
+
+    let stats = std::fs::read_to_string(temp_dir.path().join("march-stats.csv")).unwrap();
+    assert!(stats.lines().nth(1).unwrap().starts_with("2025-03-01,1,1,0,0,0,2,0.8,2,0"));
+}
+
+#[test]
+fn test_markdown_digest_groups_by_category() {
+    let (db, temp_dir) = populated_db();
+    let dest = temp_dir.path().join("digest.md");
+
+    export::export(&db, ExportFormat::Markdown, &dest, None, Some("2025-03-02")).unwrap();
+    let digest = std::fs::read_to_string(&dest).unwrap();
+
+    assert!(digest.starts_with("# Cazzmachine export\n\n_Up to 2025-03-02, generated "));
+    assert!(digest.contains("| 2025-02-20 | 1 | 0 | 0 | 1 |"));
+    let saved = &digest[digest.find("## Saved").unwrap()..digest.find("## History").unwrap()];
+    assert!(saved.find("### Memes (1)").unwrap() < saved.find("### News (1)").unwrap());
+    assert!(saved.contains("  > best loaf\n  Tags: `cats` `loaf`"));
+    let history = &digest[digest.find("## History").unwrap()..];
+    assert!(history.contains("### News (2)"));
+    assert!(history.contains("- [Why \\[did\\] the chicken](<https://example.com/joke-1>) — joke-source (2025-03-01)"));
+}
+
+#[test]
+fn test_export_rejects_bad_ranges() {
+    let (db, temp_dir) = populated_db();
+    let dest = temp_dir.path().join("out.json");
+
+    assert!(export::export(&db, ExportFormat::Json, &dest, Some("03/01/2025"), None).is_err());
+    assert!(export::export(&db, ExportFormat::Json, &dest, Some("2025-03-02"), Some("2025-03-01")).is_err());
+    assert!(!dest.exists());
+}
+
+/// Saved items are keyed by URL, the way the crawler assigns ids
+fn crawled(url: &str, category: &str, title: &str) -> CrawlItem {
+    CrawlItem {
+        id: cazzmachine_lib::crawler::provider::generate_stable_id(url),
+        url: url.to_string(),
+        ..item("unused", category, title, "2025-03-01")
+    }
+}
+
+#[test]
+fn test_bundle_round_trip_merges_and_dry_run_changes_nothing() {
+    use cazzmachine_lib::export::bundle::{self, ImportCounts};
+
+    // Source machine: two saved items, a source, a rule, a setting and a finished day
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = Database::new(source_dir.path().to_path_buf()).unwrap();
+    let shared = crawled("https://example.com/shared", "meme", "On both machines");
+    let only_there = crawled("https://example.com/only-there", "joke", "Only on the source");
+    let thumbnail = ImageData { mime: "image/gif".to_string(), bytes: b"GIF89a-only-there".to_vec() };
+    let only_there = CrawlItem { thumbnail_hash: Some(source.store_image(&thumbnail).unwrap()), ..only_there };
+    for entry in [&shared, &only_there] {
+        source.insert_item(entry).unwrap();
+        source.toggle_item_saved(&entry.id).unwrap();
+    }
+    source.set_library_note(&shared.id, Some("from the laptop")).unwrap();
+    source.set_library_tags(&shared.id, &["cats".to_string()]).unwrap();
+    source.add_source("subreddit", "rarepuppers", "meme").unwrap();
+    source.add_filter_rule("keyword", "crypto").unwrap();
+    source.set_app_state("throttle_level", "8").unwrap();
+    {
+        let conn = source.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET is_consumed = 1", []).unwrap();
+    }
+
+    let bundle_path = source_dir.path().join("backup.json");
+    let summary = bundle::write(&source, &bundle_path).unwrap();
+    assert_eq!((summary.saved_items, summary.sources, summary.filters, summary.days), (2, 1, 1, 1));
+
+    // Target machine already has the shared item saved, with a tag of its own
+    let target_dir = tempfile::tempdir().unwrap();
+    let target = Database::new(target_dir.path().to_path_buf()).unwrap();
+    target.insert_item(&shared).unwrap();
+    target.toggle_item_saved(&shared.id).unwrap();
+    target.set_library_tags(&shared.id, &["work".to_string()]).unwrap();
+    target.add_filter_rule("keyword", "crypto").unwrap();
+    // A finished local day that hasn't been rolled up yet
+    let local_day = crawled("https://example.com/local-only", "news", "Read on the target");
+    target.insert_item(&CrawlItem { session_date: "2025-02-10".to_string(), ..local_day }).unwrap();
+    {
+        let conn = target.conn.lock().unwrap();
+        conn.execute("UPDATE crawl_items SET is_consumed = 1 WHERE session_date = '2025-02-10'", []).unwrap();
+    }
+    let session_stats = |db: &Database| -> Vec<String> {
+        let conn = db.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT date FROM session_stats ORDER BY date").unwrap();
+        let dates = stmt.query_map([], |row| row.get(0)).unwrap();
+        dates.collect::<Result<_, _>>().unwrap()
+    };
+    assert!(session_stats(&target).is_empty());
+
+    let bundle = bundle::read(&bundle_path).unwrap();
+    let preview = bundle::import(&target, &bundle, true).unwrap();
+    assert!(preview.dry_run);
+    assert_eq!(preview.saved, ImportCounts { added: 1, updated: 1, unchanged: 0, skipped: 0 });
+    assert_eq!(preview.filters.unchanged, 1);
+    assert_eq!(preview.settings.added, 1);
+    assert!(target.list_sources().unwrap().is_empty(), "dry run writes nothing");
+    assert_eq!(target.get_app_state("throttle_level").unwrap(), None);
+    assert!(session_stats(&target).is_empty(), "dry run doesn't roll up local days either");
+
+    let report = bundle::import(&target, &bundle, false).unwrap();
+    assert_eq!(report.saved, preview.saved);
+    assert_eq!(report.sources.added, 1);
+    assert_eq!(report.daily_stats.added, 1);
+    assert_eq!(session_stats(&target), ["2025-02-10", "2025-03-01"]);
+    assert_eq!(target.get_app_state("throttle_level").unwrap().as_deref(), Some("8"));
+
+    let library = target.get_saved_items(&Default::default(), &Default::default()).unwrap();
+    assert_eq!(library.total, 2);
+    let merged = library.items.iter().find(|e| e.item.id == shared.id).unwrap();
+    assert_eq!(merged.note.as_deref(), Some("from the laptop"));
+    assert_eq!(merged.tags, ["cats", "work"]);
+    let imported = library.items.iter().find(|e| e.item.id == only_there.id).unwrap();
+    assert_eq!(imported.item.thumbnail_hash, only_there.thumbnail_hash);
+    assert_eq!(target.get_image(&thumbnail.hash()).unwrap(), Some(thumbnail.clone()), "thumbnails travel with the library");
+
+    // Importing the same bundle again is a no-op
+    let again = bundle::import(&target, &bundle, false).unwrap();
+    assert_eq!(again.saved, ImportCounts { added: 0, updated: 0, unchanged: 2, skipped: 0 });
+    assert_eq!(again.sources.unchanged, 1);
+    assert_eq!(again.daily_stats.unchanged, 1);
+}
+
+/// Bundles from before URLs were canonicalized carry raw URLs; imported
+/// items must get the id the crawler would give them today
+#[test]
+fn test_bundle_import_canonicalizes_urls() {
+    use cazzmachine_lib::crawler::provider::generate_stable_id;
+    use cazzmachine_lib::crawler::url::canonicalize;
+    use cazzmachine_lib::export::bundle;
+
+    let raw_url = "https://example.com/story?utm_source=feed&id=3";
+    let source_dir = tempfile::tempdir().unwrap();
+    let source = Database::new(source_dir.path().to_path_buf()).unwrap();
+    let old_item = CrawlItem {
+        id: generate_stable_id(raw_url),
+        url: raw_url.to_string(),
+        ..item("unused", "news", "Tracked story", "2025-03-01")
+    };
+    source.insert_item(&old_item).unwrap();
+    source.toggle_item_saved(&old_item.id).unwrap();
+    let bundle_path = source_dir.path().join("backup.json");
+    bundle::write(&source, &bundle_path).unwrap();
+
+    let (target, _target_dir) = populated_db();
+    let report = bundle::import(&target, &bundle::read(&bundle_path).unwrap(), false).unwrap();
+    assert_eq!(report.saved.added, 1);
+
+    // What the crawler stores when it comes across the story again
+    let recrawled = crawled(&canonicalize(raw_url), "news", "Tracked story");
+    assert_eq!(recrawled.url, "https://example.com/story?id=3");
+    let library = target.get_saved_items(&Default::default(), &Default::default()).unwrap();
+    let imported = library.items.iter().find(|e| e.item.title == "Tracked story").unwrap();
+    assert_eq!(imported.item.id, recrawled.id, "a re-crawled copy matches the imported item");
+    assert_eq!(imported.item.url, recrawled.url);
+    assert!(!target.insert_item(&recrawled).unwrap(), "and isn't stored twice");
+}
+
+/// Only settings this build knows are imported, and only within their range
+#[test]
+fn test_bundle_import_takes_only_known_settings() {
+    use cazzmachine_lib::export::bundle::{self, ImportCounts};
+
+    let (source, _source_dir) = populated_db();
+    let mut crafted = bundle::create(&source).unwrap();
+    crafted.app_state = [
+        ("throttle_level", "42"),
+        ("consumption_threads", "3"),
+        ("thumbnail_max_px", "huge"),
+        ("last_active_timestamp", "0"),
+        ("injected_key", "<script>"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+
+    let target_dir = tempfile::tempdir().unwrap();
+    let target = Database::new(target_dir.path().to_path_buf()).unwrap();
+    target.set_app_state("consumption_threads", "2").unwrap();
+    let report = bundle::import(&target, &crafted, false).unwrap();
+    assert_eq!(report.settings, ImportCounts { added: 1, updated: 1, unchanged: 0, skipped: 3 });
+    assert_eq!(target.get_app_state("throttle_level").unwrap().as_deref(), Some("9"), "clamped");
+    assert_eq!(target.get_app_state("consumption_threads").unwrap().as_deref(), Some("3"));
+    assert_eq!(target.get_app_state("thumbnail_max_px").unwrap(), None);
+    assert_ne!(target.get_app_state("last_active_timestamp").unwrap().as_deref(), Some("0"));
+    assert_eq!(target.get_app_state("injected_key").unwrap(), None);
+}
+
+#[test]
+fn test_bundle_read_rejects_foreign_files() {
+    use cazzmachine_lib::export::bundle;
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("other.json");
+    std::fs::write(&path, r#"{"hello": "world"}"#).unwrap();
+    assert!(bundle::read(&path).is_err());
+
+    let (db, _db_dir) = populated_db();
+    bundle::write(&db, &path).unwrap();
+    let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    json["bundle_version"] = serde_json::json!(bundle::BUNDLE_VERSION + 1);
+    std::fs::write(&path, json.to_string()).unwrap();
+    assert!(bundle::read(&path).unwrap_err().contains("newer"));
+}
@@ -0,0 +1,51 @@
+-- Database as created by builds before schema versioning (user_version 0):
+-- crawl_items predates thumbnail_data and is_consumed, no diagnostic_logs.
+CREATE TABLE crawl_items (
+    id TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    category TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL UNIQUE,
+    thumbnail_url TEXT,
+    description TEXT,
+    fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
+    is_seen INTEGER NOT NULL DEFAULT 0,
+    is_saved INTEGER NOT NULL DEFAULT 0,
+    session_date TEXT NOT NULL DEFAULT (date('now'))
+);
+CREATE INDEX idx_crawl_items_session ON crawl_items(session_date);
+CREATE INDEX idx_crawl_items_category ON crawl_items(category);
+
+CREATE TABLE session_stats (
+    id TEXT PRIMARY KEY,
+    date TEXT NOT NULL UNIQUE,
+    started_at TEXT NOT NULL,
+    memes_found INTEGER NOT NULL DEFAULT 0,
+    jokes_found INTEGER NOT NULL DEFAULT 0,
+    news_checked INTEGER NOT NULL DEFAULT 0,
+    videos_found INTEGER NOT NULL DEFAULT 0,
+    gossip_found INTEGER NOT NULL DEFAULT 0,
+    total_items INTEGER NOT NULL DEFAULT 0,
+    estimated_time_saved_minutes REAL NOT NULL DEFAULT 0.0
+);
+
+CREATE TABLE notification_log (
+    id TEXT PRIMARY KEY,
+    message TEXT NOT NULL,
+    sent_at TEXT NOT NULL DEFAULT (datetime('now')),
+    related_item_id TEXT,
+    FOREIGN KEY (related_item_id) REFERENCES crawl_items(id)
+);
+
+CREATE TABLE app_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+INSERT INTO app_state (key, value) VALUES ('last_active_timestamp', '1717000000000');
+INSERT INTO app_state (key, value) VALUES ('throttle_level', '7');
+
+INSERT INTO crawl_items (id, source, category, title, url, fetched_at, is_seen, is_saved, session_date)
+VALUES ('old-1', 'r/memes', 'meme', 'Old meme', 'https://reddit.com/r/memes/comments/old1/', '2024-05-30 10:00:00', 1, 1, '2024-05-30');
+INSERT INTO crawl_items (id, source, category, title, url, description, fetched_at, session_date)
+VALUES ('old-2', 'icanhazdadjoke', 'joke', 'Old joke', 'https://icanhazdadjoke.com/j/old2', 'Old joke', '2024-05-30 10:05:00', '2024-05-30');
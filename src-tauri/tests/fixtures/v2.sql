@@ -0,0 +1,83 @@
+-- Database at schema version 2 (baseline + provider_runs).
+CREATE TABLE crawl_items (
+    id TEXT PRIMARY KEY,
+    source TEXT NOT NULL,
+    category TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT NOT NULL UNIQUE,
+    thumbnail_url TEXT,
+    thumbnail_data TEXT,
+    description TEXT,
+    fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
+    is_seen INTEGER NOT NULL DEFAULT 0,
+    is_saved INTEGER NOT NULL DEFAULT 0,
+    is_consumed INTEGER NOT NULL DEFAULT 0,
+    session_date TEXT NOT NULL DEFAULT (date('now'))
+);
+CREATE INDEX idx_crawl_items_session ON crawl_items(session_date);
+CREATE INDEX idx_crawl_items_category ON crawl_items(category);
+CREATE INDEX idx_crawl_items_consumed ON crawl_items(is_consumed);
+
+CREATE TABLE session_stats (
+    id TEXT PRIMARY KEY,
+    date TEXT NOT NULL UNIQUE,
+    started_at TEXT NOT NULL,
+    memes_found INTEGER NOT NULL DEFAULT 0,
+    jokes_found INTEGER NOT NULL DEFAULT 0,
+    news_checked INTEGER NOT NULL DEFAULT 0,
+    videos_found INTEGER NOT NULL DEFAULT 0,
+    gossip_found INTEGER NOT NULL DEFAULT 0,
+    total_items INTEGER NOT NULL DEFAULT 0,
+    estimated_time_saved_minutes REAL NOT NULL DEFAULT 0.0
+);
+
+CREATE TABLE notification_log (
+    id TEXT PRIMARY KEY,
+    message TEXT NOT NULL,
+    sent_at TEXT NOT NULL DEFAULT (datetime('now')),
+    related_item_id TEXT,
+    FOREIGN KEY (related_item_id) REFERENCES crawl_items(id)
+);
+
+CREATE TABLE app_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+INSERT INTO app_state (key, value) VALUES ('last_active_timestamp', '1717000000000');
+
+CREATE TABLE diagnostic_logs (
+    id TEXT PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    message TEXT NOT NULL,
+    related_item_id TEXT,
+    metadata TEXT
+);
+CREATE INDEX idx_diagnostic_logs_severity ON diagnostic_logs(severity);
+CREATE INDEX idx_diagnostic_logs_event ON diagnostic_logs(event_type);
+
+CREATE TABLE provider_runs (
+    id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    items_fetched INTEGER NOT NULL DEFAULT 0,
+    items_new INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    status_code INTEGER
+);
+CREATE INDEX idx_provider_runs_provider ON provider_runs(provider, started_at);
+
+INSERT INTO crawl_items (id, source, category, title, url, thumbnail_data, fetched_at, is_consumed, session_date)
+VALUES ('v2-1', 'BBC News', 'news', 'Some news', 'https://www.bbc.co.uk/news/v2-1', NULL, '2025-01-10 09:00:00', 0, '2025-01-10');
+INSERT INTO crawl_items (id, source, category, title, url, thumbnail_data, fetched_at, is_consumed, session_date)
+VALUES ('v2-2', 'r/memes', 'meme', 'Some meme', 'https://reddit.com/r/memes/comments/v2', 'data:image/gif;base64,R0lGODlhAQABAAAAACw=', '2025-01-10 09:05:00', 1, '2025-01-10');
+INSERT INTO crawl_items (id, source, category, title, url, thumbnail_data, fetched_at, is_consumed, session_date)
+VALUES ('v2-3', 'r/dankmemes', 'meme', 'Same meme', 'https://reddit.com/r/dankmemes/comments/v2', 'data:image/gif;base64,R0lGODlhAQABAAAAACw=', '2025-01-10 09:06:00', 1, '2025-01-10');
+INSERT INTO provider_runs (id, provider, started_at, duration_ms, outcome, items_fetched, items_new)
+VALUES ('run-1', 'bbc-news', '2025-01-10T09:00:00.000', 420, 'success', 8, 1);
+
+PRAGMA user_version = 2;
@@ -0,0 +1,148 @@
+//! Schema migration tests
+//!
+//! Each fixture in `tests/fixtures` is the SQL dump of a database created by
+//! an older build. The tests load a fixture, open it through `Database::new`
+//! and check that it is upgraded to the latest schema with its data intact.
+
+use cazzmachine_lib::db::migrations::{self, MIGRATIONS};
+use cazzmachine_lib::db::Database;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+
+/// Writes `cazzmachine.db` in a fresh app dir from the named fixture.
+fn app_dir_from_fixture(fixture: &str) -> tempfile::TempDir {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(fixture);
+    let sql = std::fs::read_to_string(&path).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let conn = Connection::open(temp_dir.path().join("cazzmachine.db")).unwrap();
+    conn.execute_batch(&sql).unwrap();
+    temp_dir
+}
+
+fn user_version(db: &Database) -> i32 {
+    migrations::current_version(&db.conn.lock().unwrap()).unwrap()
+}
+
+fn columns(db: &Database, table: &str) -> Vec<String> {
+    let conn = db.conn.lock().unwrap();
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+    let names = stmt
+        .query_map([], |row| row.get(1))
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    names
+}
+
+fn backups(app_dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(app_dir.join("backups"))
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_migration_versions_are_consecutive() {
+    for (i, migration) in MIGRATIONS.iter().enumerate() {
+        assert_eq!(migration.version, i as i32 + 1, "{}", migration.description);
+    }
+    assert_eq!(migrations::latest_version(), MIGRATIONS.len() as i32);
+}
+
+#[test]
+fn test_fresh_database_is_created_at_latest_version_without_backup() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(user_version(&db), migrations::latest_version());
+    assert!(backups(temp_dir.path()).is_empty());
+}
+
+#[test]
+fn test_unversioned_database_is_upgraded() {
+    let temp_dir = app_dir_from_fixture("v0_unversioned.sql");
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(user_version(&db), migrations::latest_version());
+    let item_columns = columns(&db, "crawl_items");
+    assert!(item_columns.contains(&"thumbnail_hash".to_string()));
+    assert!(!item_columns.contains(&"thumbnail_data".to_string()), "thumbnails live in the image store");
+    assert!(item_columns.contains(&"is_consumed".to_string()));
+    assert!(!columns(&db, "diagnostic_logs").is_empty());
+    assert!(!columns(&db, "filter_rules").is_empty());
+
+    // Existing rows survive and pick up the backfilled defaults
+    let unconsumed: i64 = db
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM crawl_items WHERE is_consumed = 0", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(unconsumed, 2);
+    assert_eq!(db.get_app_state("throttle_level").unwrap().as_deref(), Some("7"));
+    db.log_diagnostic_event("test", "info", "diagnostics table exists", None, None).unwrap();
+
+    // Items saved before the library existed are carried into it
+    let library = db
+        .get_saved_items(&Default::default(), &Default::default())
+        .unwrap();
+    assert_eq!(library.total, 1);
+    assert_eq!(library.items[0].item.id, "old-1");
+    assert_eq!(library.items[0].saved_at, "2024-05-30 10:00:00");
+    assert_eq!(library.items[0].item.metadata, Default::default(), "old items have no metadata");
+
+    let saved = backups(temp_dir.path());
+    assert_eq!(saved.len(), 1);
+    let backup = Connection::open(&saved[0]).unwrap();
+    let backed_up_items: i64 = backup
+        .query_row("SELECT COUNT(*) FROM crawl_items", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(backed_up_items, 2);
+    let backup_version: i32 = backup.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(backup_version, 0, "the backup is taken before migrating");
+}
+
+#[test]
+fn test_versioned_database_only_runs_pending_steps() {
+    let temp_dir = app_dir_from_fixture("v2.sql");
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(user_version(&db), migrations::latest_version());
+    assert!(!columns(&db, "sources").is_empty());
+    let runs: i64 = db
+        .conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM provider_runs", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(runs, 1);
+
+    // Inline thumbnails are moved into the image store, once per distinct image
+    let hashes: Vec<Option<String>> = {
+        let conn = db.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT thumbnail_hash FROM crawl_items ORDER BY id").unwrap();
+        let rows = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+        rows
+    };
+    assert_eq!(hashes[0], None);
+    assert_eq!(hashes[1], hashes[2]);
+    let image = db.get_image(hashes[1].as_deref().unwrap()).unwrap().unwrap();
+    assert_eq!(image.mime, "image/gif");
+    assert!(image.bytes.starts_with(b"GIF89a"));
+    assert_eq!(db.image_store_size().unwrap(), image.bytes.len() as i64);
+    assert!(backups(temp_dir.path())[0]
+        .to_string_lossy()
+        .ends_with("-v2.db"));
+}
+
+#[test]
+fn test_reopening_an_up_to_date_database_is_a_no_op() {
+    let temp_dir = app_dir_from_fixture("v0_unversioned.sql");
+    drop(Database::new(temp_dir.path().to_path_buf()).unwrap());
+    let db = Database::new(temp_dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(user_version(&db), migrations::latest_version());
+    assert_eq!(backups(temp_dir.path()).len(), 1, "no backup without pending migrations");
+}
@@ -10,7 +10,7 @@ import { DetailView } from "./components/DetailView";
 import { ErrorBoundary } from "./components/ErrorBoundary";
 import { DiagnosticsPanel } from "./components/DiagnosticsPanel";
 import { SplashScreen } from "./components/SplashScreen";
-import { getThrottleLevel, triggerCrawl } from "./lib/tauri";
+import { getSettings, triggerCrawl } from "./lib/tauri";
 
 function App() {
   useNotifications();
@@ -19,13 +19,17 @@ function App() {
   const debugMode = useAppStore((s) => s.debugMode);
   const isFirstRun = useAppStore((s) => s.isFirstRun);
   const setThrottleLevel = useAppStore((s) => s.setThrottleLevel);
+  const setThreadCount = useAppStore((s) => s.setThreadCount);
   const [showDiagnostics, setShowDiagnostics] = useState(false);
 
   useEffect(() => {
-    getThrottleLevel().then(async (level) => {
-      const currentLevel = useAppStore.getState().throttleLevel;
-      if (level !== currentLevel) {
-        await setThrottleLevel(level);
+    getSettings().then(async (settings) => {
+      const { throttleLevel, threadCount } = useAppStore.getState();
+      if (settings.throttle_level !== throttleLevel) {
+        await setThrottleLevel(settings.throttle_level);
+      }
+      if (settings.consumption_threads !== threadCount) {
+        await setThreadCount(settings.consumption_threads);
       }
       const firstRun = useAppStore.getState().isFirstRun;
       if (!firstRun) {
@@ -34,7 +38,7 @@ function App() {
         });
       }
     });
-  }, [setThrottleLevel]);
+  }, [setThrottleLevel, setThreadCount]);
 
   useEffect(() => {
     const params = new URLSearchParams(window.location.search);
@@ -22,7 +22,7 @@ interface Props {
 
 export function CrawlItemCard({ item }: Props) {
   const [saved, setSaved] = useState(item.is_saved);
-  const imageUrl = useThumbnailUrl(item.thumbnail_data, item.thumbnail_url, { onError: 'silent' });
+  const imageUrl = useThumbnailUrl(item.thumbnail_hash, item.thumbnail_url);
   const isDoneWorking = useAppStore((s) => s.isDoneWorking);
   const hoveredItem = useAppStore((s) => s.hoveredItem);
   const setHoveredItem = useAppStore((s) => s.setHoveredItem);
@@ -9,11 +9,7 @@ export function HoverPreview() {
   const setHoveredItem = useAppStore((s) => s.setHoveredItem);
   const clearHoverTimeout = useAppStore((s) => s.clearHoverTimeout);
   const setHoverTimeout = useAppStore((s) => s.setHoverTimeout);
-  const imageUrl = useThumbnailUrl(
-    hoveredItem?.thumbnail_data,
-    hoveredItem?.thumbnail_url,
-    { onError: 'null' }
-  );
+  const imageUrl = useThumbnailUrl(hoveredItem?.thumbnail_hash, hoveredItem?.thumbnail_url);
 
   if (!hoveredItem || !isDoneWorking) return null;
 
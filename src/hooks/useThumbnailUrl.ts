@@ -1,41 +1,24 @@
-import { useState, useEffect } from 'react';
-import { fetchImage } from '../lib/tauri';
-
-interface UseThumbnailUrlOptions {
-  onError?: 'silent' | 'null';
-}
+import { useMemo } from 'react';
+import { thumbnailSrc } from '../lib/tauri';
 
+/**
+ * Image source for an item's thumbnail. Stored and remote thumbnails are both
+ * loaded through the `cazz` image scheme; other values of `thumbnailUrl`
+ * (data URLs, relative paths) are used as they are.
+ */
 export function useThumbnailUrl(
-  thumbnailData: string | null | undefined,
-  thumbnailUrl: string | null | undefined,
-  options: UseThumbnailUrlOptions = { onError: 'null' }
+  thumbnailHash: string | null | undefined,
+  thumbnailUrl: string | null | undefined
 ): string | null {
-  const [imageUrl, setImageUrl] = useState<string | null>(thumbnailData || null);
-
-  useEffect(() => {
-    setImageUrl(null);
-
-    if (thumbnailData) {
-      setImageUrl(thumbnailData);
-    } else if (thumbnailUrl) {
-      let url = thumbnailUrl;
-      if (url.startsWith('//')) {
-        url = 'https:' + url;
-      }
-      if (url.startsWith('http://') || url.startsWith('https://')) {
-        fetchImage(url)
-          .then(setImageUrl)
-          .catch(() => {
-            if (options.onError === 'null') {
-              setImageUrl(null);
-            }
-            // If 'silent', do nothing (image fails silently)
-          });
-      } else {
-        setImageUrl(url);
-      }
+  return useMemo(() => {
+    const remote =
+      thumbnailUrl &&
+      (thumbnailUrl.startsWith('//') ||
+        thumbnailUrl.startsWith('http://') ||
+        thumbnailUrl.startsWith('https://'));
+    if (thumbnailHash || remote) {
+      return thumbnailSrc(thumbnailHash ?? null, remote ? thumbnailUrl : null);
     }
-  }, [thumbnailData, thumbnailUrl, options.onError]);
-
-  return imageUrl;
+    return thumbnailUrl || null;
+  }, [thumbnailHash, thumbnailUrl]);
 }
@@ -8,12 +8,23 @@ export interface CrawlItem {
   title: string;
   url: string;
   thumbnail_url: string | null;
-  thumbnail_data: string | null;
+  /** Key of the downloaded thumbnail in the image store; load it with `thumbnailSrc`. */
+  thumbnail_hash: string | null;
   description: string | null;
   fetched_at: string;
   is_seen: boolean;
   is_saved: boolean;
   session_date: string;
+  metadata: ItemMetadata;
+}
+
+export interface ItemMetadata {
+  score: number | null;
+  comments: number | null;
+  author: string | null;
+  published_at: string | null;
+  media_duration_secs: number | null;
+  domain: string | null;
 }
 
 export interface DayStats {
@@ -26,6 +37,26 @@ export interface DayStats {
   estimated_time_saved_minutes: number;
 }
 
+export interface DailyStats {
+  date: string;
+  stats: DayStats;
+  providers_used: number;
+  consume_calls: number;
+}
+
+export type StatsPeriod = "week" | "month" | "all";
+
+export interface StatsSummary {
+  period: StatsPeriod;
+  from_date: string;
+  to_date: string;
+  days_active: number;
+  totals: DayStats;
+  consume_calls: number;
+  average_items_per_day: number;
+  average_providers_per_day: number;
+}
+
 export interface DaySummary {
   stats: DayStats;
   summary_text: string;
@@ -41,6 +72,7 @@ export interface ConsumeResult {
   news_consumed: number;
   videos_consumed: number;
   gossip_consumed: number;
+  strategy: ConsumptionStrategy;
 }
 
 export interface DiagnosticLog {
@@ -72,6 +104,146 @@ export interface ProviderStatus {
   last_fetch_status: string;
   last_fetch_timestamp: string | null;
   recent_error_count: number;
+  last_error: string | null;
+  last_error_kind: string | null;
+  last_status_code: number | null;
+  last_latency_ms: number | null;
+  total_runs: number;
+  success_rate: number;
+  median_latency_ms: number | null;
+  consecutive_failures: number;
+}
+
+export type ConsumptionStrategy = "fifo" | "round_robin" | "engagement_first" | "random";
+
+export interface CostModel {
+  categories: Record<string, number>;
+  sources: Record<string, number>;
+  default_minutes: number;
+  reading_words_per_minute: number | null;
+  text_categories: string[];
+}
+
+/** A token bucket: `burst` requests back to back, refilled at `per_minute`. */
+export interface RateLimit {
+  burst: number;
+  per_minute: number;
+}
+
+export interface Settings {
+  throttle_level: number;
+  consumption_threads: number;
+  consumption_strategy: ConsumptionStrategy;
+  cost_model: CostModel;
+  /** Longest side, in pixels, downloaded thumbnails are scaled down to (64-2048). */
+  thumbnail_max_px: number;
+  /** Request budget overrides, by provider name and then host. */
+  rate_limits: Record<string, Record<string, RateLimit>>;
+}
+
+export type SourceKind = "subreddit" | "feed" | "json_joke";
+
+export interface Source {
+  id: string;
+  kind: SourceKind;
+  target: string;
+  category: Category;
+  enabled: boolean;
+  created_at: string;
+}
+
+export type FilterKind = "keyword" | "regex" | "domain" | "subreddit";
+
+export interface FilterRule {
+  id: string;
+  kind: FilterKind;
+  pattern: string;
+  enabled: boolean;
+  hit_count: number;
+  created_at: string;
+}
+
+export interface SearchFilters {
+  category?: Category | null;
+  source?: string | null;
+  saved_only?: boolean;
+  from_date?: string | null;
+  to_date?: string | null;
+}
+
+export interface Paging {
+  offset: number;
+  limit: number;
+}
+
+/** `title_highlighted` and `snippet` are HTML-escaped, with matched terms wrapped in <mark>. */
+export interface SearchHit {
+  item: CrawlItem;
+  title_highlighted: string;
+  snippet: string;
+  rank: number;
+}
+
+export interface SearchResults {
+  hits: SearchHit[];
+  total: number;
+}
+
+export interface LibraryItem {
+  item: CrawlItem;
+  saved_at: string;
+  note: string | null;
+  tags: string[];
+}
+
+/** Dates are YYYY-MM-DD and inclusive; they apply to saved_at. */
+export interface LibraryFilters {
+  category?: Category | null;
+  tag?: string | null;
+  from_date?: string | null;
+  to_date?: string | null;
+}
+
+export interface LibraryPage {
+  items: LibraryItem[];
+  total: number;
+}
+
+export type ExportFormat = "json" | "csv" | "markdown";
+
+export interface ExportReport {
+  format: ExportFormat;
+  files: string[];
+  saved_items: number;
+  history_items: number;
+  days: number;
+}
+
+export interface BundleSummary {
+  path: string;
+  created_at: string;
+  schema_version: number;
+  settings: number;
+  saved_items: number;
+  sources: number;
+  filters: number;
+  days: number;
+}
+
+export interface ImportCounts {
+  added: number;
+  updated: number;
+  unchanged: number;
+  skipped: number;
+}
+
+export interface ImportReport {
+  dry_run: boolean;
+  settings: ImportCounts;
+  saved: ImportCounts;
+  sources: ImportCounts;
+  filters: ImportCounts;
+  daily_stats: ImportCounts;
 }
 
 export interface ClearDiagnosticsResult {
@@ -90,10 +262,31 @@ export async function getItemsByCategory(
   return invoke<CrawlItem[]>("get_items_by_category", { category });
 }
 
+export async function searchItems(
+  query: string,
+  filters?: SearchFilters,
+  paging?: Paging
+): Promise<SearchResults> {
+  return invoke<SearchResults>("search_items", {
+    query,
+    filters: filters ?? null,
+    paging: paging ?? null,
+  });
+}
+
 export async function getTodayStats(): Promise<DayStats> {
   return invoke<DayStats>("get_today_stats");
 }
 
+export async function getStatsSummary(period: StatsPeriod): Promise<StatsSummary> {
+  return invoke<StatsSummary>("get_stats_summary", { period });
+}
+
+/** One entry per day, oldest first, ending with today. */
+export async function getStatsSeries(period: StatsPeriod): Promise<DailyStats[]> {
+  return invoke<DailyStats[]>("get_stats_series", { period });
+}
+
 export async function getDailySummary(): Promise<DaySummary> {
   return invoke<DaySummary>("get_daily_summary");
 }
@@ -102,6 +295,70 @@ export async function toggleSaveItem(itemId: string): Promise<boolean> {
   return invoke<boolean>("toggle_save_item", { itemId });
 }
 
+export async function getSavedItems(
+  filters?: LibraryFilters,
+  paging?: Paging
+): Promise<LibraryPage> {
+  return invoke<LibraryPage>("get_saved_items", {
+    filters: filters ?? null,
+    paging: paging ?? null,
+  });
+}
+
+export async function setSavedItemNote(
+  itemId: string,
+  note: string | null
+): Promise<void> {
+  return invoke<void>("set_saved_item_note", { itemId, note });
+}
+
+export async function setSavedItemTags(
+  itemId: string,
+  tags: string[]
+): Promise<string[]> {
+  return invoke<string[]>("set_saved_item_tags", { itemId, tags });
+}
+
+export interface ItemDuplicate {
+  id: string;
+  canonical_id: string;
+  source: string;
+  title: string;
+  url: string;
+  reason: "url" | "title" | "image";
+  similarity: number;
+  detected_at: string;
+}
+
+/** Copies of an item crawled from other sources, which were not stored. */
+export async function getItemDuplicates(itemId: string): Promise<ItemDuplicate[]> {
+  return invoke<ItemDuplicate[]>("get_item_duplicates", { itemId });
+}
+
+/** CSV writes `<stem>-saved.csv`, `<stem>-history.csv` and `<stem>-stats.csv` next to `path`. */
+export async function exportData(
+  format: ExportFormat,
+  path: string,
+  fromDate?: string,
+  toDate?: string
+): Promise<ExportReport> {
+  return invoke<ExportReport>("export_data", {
+    format,
+    path,
+    fromDate: fromDate ?? null,
+    toDate: toDate ?? null,
+  });
+}
+
+export async function exportBundle(path: string): Promise<BundleSummary> {
+  return invoke<BundleSummary>("export_bundle", { path });
+}
+
+/** With dryRun, reports what would change without touching the database. */
+export async function importBundle(path: string, dryRun: boolean): Promise<ImportReport> {
+  return invoke<ImportReport>("import_bundle", { path, dryRun });
+}
+
 export async function markItemSeen(itemId: string): Promise<void> {
   return invoke<void>("mark_item_seen", { itemId });
 }
@@ -110,6 +367,19 @@ export async function fetchImage(url: string): Promise<string> {
   return invoke<string>("fetch_image", { url });
 }
 
+/**
+ * URL the webview loads a thumbnail from: the `cazz` scheme serves it from the
+ * image store by hash, downloading and storing `src` when it isn't stored.
+ * Windows and Android webviews reach custom schemes over `http://<scheme>.localhost`.
+ */
+export function thumbnailSrc(hash: string | null, src: string | null): string {
+  const base = /Windows|Android/.test(navigator.userAgent)
+    ? "http://cazz.localhost/image/"
+    : "cazz://image/";
+  const query = src ? `?src=${encodeURIComponent(src)}` : "";
+  return `${base}${hash ?? ""}${query}`;
+}
+
 declare global {
   interface Window {
     Tauri?: any;
@@ -156,6 +426,14 @@ export async function setConsumptionThreads(count: number): Promise<void> {
   return invoke<void>("set_consumption_threads", { count });
 }
 
+export async function getSettings(): Promise<Settings> {
+  return invoke<Settings>("get_settings");
+}
+
+export async function updateSettings(settings: Settings): Promise<Settings> {
+  return invoke<Settings>("update_settings", { settings });
+}
+
 export async function getPendingCount(): Promise<number> {
   return invoke<number>("get_pending_count");
 }
@@ -212,6 +490,42 @@ export async function triggerCrawl(): Promise<number> {
   return invoke<number>("trigger_crawl");
 }
 
+export async function listSources(): Promise<Source[]> {
+  return invoke<Source[]>("list_sources");
+}
+
+export async function addSource(
+  kind: SourceKind,
+  target: string,
+  category: Category
+): Promise<Source> {
+  return invoke<Source>("add_source", { kind, target, category });
+}
+
+export async function removeSource(sourceId: string): Promise<void> {
+  return invoke<void>("remove_source", { sourceId });
+}
+
+export async function setSourceEnabled(sourceId: string, enabled: boolean): Promise<void> {
+  return invoke<void>("set_source_enabled", { sourceId, enabled });
+}
+
+export async function listFilterRules(): Promise<FilterRule[]> {
+  return invoke<FilterRule[]>("list_filter_rules");
+}
+
+export async function addFilterRule(kind: FilterKind, pattern: string): Promise<FilterRule> {
+  return invoke<FilterRule>("add_filter_rule", { kind, pattern });
+}
+
+export async function removeFilterRule(ruleId: string): Promise<void> {
+  return invoke<void>("remove_filter_rule", { ruleId });
+}
+
+export async function setFilterRuleEnabled(ruleId: string, enabled: boolean): Promise<void> {
+  return invoke<void>("set_filter_rule_enabled", { ruleId, enabled });
+}
+
 export async function logDiagnostic(
   eventType: string,
   severity: "info" | "warn" | "error" | "debug",